@@ -12,7 +12,7 @@ pub struct User {
     pub password: String,
 }
 
-const TABLE: &'static str = "CREATE TABLE users (
+const TABLE: &str = "CREATE TABLE users (
  `uuid` VARCHAR(65535) PRIMARY KEY NOT NULL UNIQUE,
  `created_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
  `first_name` VARCHAR(65535),
@@ -26,6 +26,7 @@ const TABLE: &'static str = "CREATE TABLE users (
 async fn main() -> Result<()> {
     let db = DB::db_with_url::<SQLite>(":memory:").await;
 
+    db.execute(TABLE).await?;
 
     // db.query()
 
@@ -0,0 +1,48 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names)]
+
+mod model;
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+/// Implements `flyer_orm::Model` for a struct.
+///
+/// ```ignore
+/// #[derive(sqlx::FromRow, Model)]
+/// #[orm(table = "users", primary_key = "uuid")]
+/// pub struct User { ... }
+/// ```
+///
+/// `#[orm(key_type = "uuid")]`, `"uuid_v7"` or `"ulid"` generates the primary key of new rows instead of the database.
+/// `#[orm(timestamps)]` fills `created_at`/`updated_at`, rename them with `#[orm(created_at = "...", updated_at = "...")]`.
+/// `#[orm(soft_deletes)]` or `#[orm(deleted_at = "...")]` makes `delete` set the column instead of removing the row.
+///
+/// Relations loaded with `Query::with` are `#[sqlx(skip)]` fields named after the relation:
+/// `#[orm(has_many = "user_id")] orders: Vec<Order>`, `#[orm(has_one = "user_id")]` and `#[orm(belongs_to = "user_id")]`
+/// on an `Option<R>`, and `#[orm(belongs_to_many(pivot = "role_user", foreign_key = "user_id", related_key = "role_id"))]`.
+/// The related models are read with their `FromRow`, which has to decode the rows of every driver, see `flyer_orm::Decodable`.
+/// Polymorphic relations use `{name}_type` and `{name}_id` columns: `#[orm(morph_many = "commentable")] comments: Vec<Comment>`,
+/// `#[orm(morph_one = "...")]` on an `Option<R>` and `#[orm(morph_to)] commentable: Option<Morphed>`. The type column
+/// holds the `MORPH_NAME` of the model, the table name unless set with `#[orm(morph_name = "post")]`.
+/// `#[orm(fillable("name", "email"))]` and `#[orm(guarded("is_admin"))]` limit the columns `create`, `insert_with`,
+/// `update_with` and `upsert_with` take from client values, the others are dropped or, with `#[orm(reject_guarded)]`, fail with `MassAssignment`.
+/// `#[orm(hidden("password"))]` leaves fields out when the model is serialized, `#[orm(visible("uuid", "email"))]` keeps
+/// only those and `#[orm(serialize_as = "firstName")]` renames a field, with any of them the derive implements `Serialize`
+/// in place of `#[derive(Serialize)]` and rejects `#[serde(...)]` attributes, which it would ignore.
+/// `Model::make_visible` and `make_hidden` change the fields for one response.
+/// `#[orm(cast = "json")]` converts a field from and to its column, with the `json`, `enum`, `bool`, `datetime` and
+/// `comma` casts of `flyer_orm::cast` or the path of a custom `Cast`. Models with casts get `FromRow` from the derive
+/// in place of `#[derive(sqlx::FromRow)]`, so a SQLite text `created_at` can be read as a `DateTime<Utc>`.
+/// `#[orm(encrypted)]` stores a `String` field encrypted with the keys of `flyer_orm::encryption`, add
+/// `blind_index = "email_index"` to keep a hash of it in that column so `where("email", "=", ...)` still matches.
+/// `#[orm(version)]` on an integer field makes `save` increment it and only update the row still at that version,
+/// failing with `StaleModel` when another save got there first. Query updates increment it too, and check it with `versioned`.
+/// `#[orm(computed)]` fields, like the `orders_count` of `with_count("orders")`, are read from the row but never written.
+#[proc_macro_derive(Model, attributes(orm, sqlx))]
+pub fn derive_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    return model::expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into();
+}
@@ -0,0 +1,734 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Result, parse_quote, punctuated::Punctuated};
+
+struct Options {
+    table: String,
+    primary_key: String,
+    /// `#[orm(key_type = "...")]`, the `KeyType` variant generating the primary key.
+    key_type: Option<syn::Ident>,
+    timestamps: bool,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    soft_deletes: bool,
+    deleted_at: Option<String>,
+    morph_name: Option<String>,
+    /// `#[orm(hidden("..."))]` and `#[orm(visible("..."))]` field names.
+    hidden: Vec<String>,
+    visible: Vec<String>,
+    /// `#[orm(fillable("..."))]` and `#[orm(guarded("..."))]` column names.
+    fillable: Vec<String>,
+    guarded: Vec<String>,
+    reject_guarded: bool,
+}
+
+struct Field {
+    ident: syn::Ident,
+    ty: syn::Type,
+    /// The column name, `None` for `#[sqlx(skip)]`, `Original` and relation fields.
+    column: Option<String>,
+    /// `#[sqlx(default)]`, the field is defaulted when its column is not selected.
+    default: bool,
+    /// `#[orm(computed)]`, selected by a query like `with_count` but not a column of the table.
+    computed: bool,
+    relation: Option<Relation>,
+    /// `#[orm(serialize_as = "...")]`, the key of the field when serialized.
+    serialize_as: Option<String>,
+    /// `#[orm(cast = "...")]`, the `Cast` type converting the field from and to its column value.
+    cast: Option<syn::Path>,
+    /// `#[orm(encrypted)]`, the field is encrypted when written and compared as plain text by dirty tracking.
+    encrypted: bool,
+    /// `#[orm(blind_index = "...")]` of an encrypted field, the column holding the hash `where` compares.
+    blind_index: Option<String>,
+    /// `#[orm(version)]`, the integer column checked and incremented by `save`.
+    version: bool,
+}
+
+/// `#[orm(has_many = "...")]` and friends, the string is the foreign key.
+enum Relation {
+    HasMany(String),
+    HasOne(String),
+    BelongsTo(String),
+    BelongsToMany { pivot: String, foreign_key: String, related_key: String },
+    /// The string is the morph name of the `{name}_type` and `{name}_id` columns.
+    MorphMany(String),
+    MorphOne(String),
+    MorphTo,
+}
+
+struct Column<'f> {
+    name: &'f String,
+    ident: &'f syn::Ident,
+    ty: &'f syn::Type,
+    cast: Option<&'f syn::Path>,
+    encrypted: bool,
+    blind_index: Option<&'f String>,
+}
+
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let options = options(&input)?;
+    let fields = fields(&input)?;
+    let columns = fields.iter()
+        .filter(|field| !field.computed)
+        .filter_map(|field| field.column.as_ref().map(|name| Column { name: name, ident: &field.ident, ty: &field.ty, cast: field.cast.as_ref(), encrypted: field.encrypted, blind_index: field.blind_index.as_ref() }))
+        .collect::<Vec<_>>();
+    let original = original(&input);
+
+    let table = &options.table;
+    let primary_key = &options.primary_key;
+    let names = columns.iter().map(|column| column.name).collect::<Vec<_>>();
+    let idents = columns.iter().map(|column| column.ident).collect::<Vec<_>>();
+    let encode = columns.iter().map(|column| {
+        let (ident, ty) = (column.ident, column.ty);
+
+        // Encrypted fields are encrypted by `cast_values` when written, so `Original` compares them as plain text.
+        return match column.cast.filter(|_| !column.encrypted) {
+            Some(cast) => quote! { <#cast as ::flyer_orm::cast::Cast<#ty>>::encode(&self.#ident)? },
+            None => quote! { ::flyer_orm::Value::from(::std::clone::Clone::clone(&self.#ident)) },
+        };
+    });
+    let indexed = columns.iter().filter(|column| column.blind_index.is_some()).collect::<Vec<_>>();
+    let blind_indexes = (!indexed.is_empty()).then(|| {
+        let (encrypted, indexes) = indexed.iter().map(|column| (column.name, column.blind_index.unwrap())).unzip::<_, _, Vec<_>, Vec<_>>();
+
+        quote! { const BLIND_INDEXES: &'static [(&'static str, &'static str)] = &[#((#encrypted, #indexes)),*]; }
+    });
+    let cast_values = columns.iter().any(|column| column.cast.is_some()).then(|| {
+        let recast = columns.iter().filter_map(|column| column.cast.map(|cast| (column.name, column.ty, cast))).map(|(name, ty, cast)| quote! {
+            #name => ::flyer_orm::cast::recast::<#cast, #ty>(#name, ::std::mem::replace(value, ::flyer_orm::Value::Null))?,
+        });
+        let (encrypted, indexes) = indexed.iter().map(|column| (column.name, column.blind_index.unwrap())).unzip::<_, _, Vec<_>, Vec<_>>();
+
+        quote! {
+            fn cast_values(values: ::std::vec::Vec<(::std::string::String, ::flyer_orm::Value)>) -> ::flyer_orm::anyhow::Result<::std::vec::Vec<(::std::string::String, ::flyer_orm::Value)>> {
+                let mut values = values.into_iter()
+                    .filter(|(column, _)| ![#(#indexes),*].contains(&column.as_str()))
+                    .collect::<::std::vec::Vec<_>>();
+
+                for (column, value) in values.iter_mut() {
+                    *value = match column.as_str() {
+                        #(#recast)*
+                        _ => continue,
+                    };
+                }
+
+                #(
+                    if let ::std::option::Option::Some((_, value)) = values.iter().find(|(column, _)| column == #encrypted) {
+                        let index = ::flyer_orm::encryption::index(value)?;
+
+                        values.push((::std::string::String::from(#indexes), index));
+                    }
+                )*
+
+                return ::std::result::Result::Ok(values);
+            }
+        }
+    });
+    let decode = columns.iter().map(|column| {
+        let ty = column.ty;
+
+        return match column.cast {
+            Some(cast) => quote! { <#cast as ::flyer_orm::cast::Cast<#ty>>::decode(value)? },
+            None => quote! { ::flyer_orm::value::FromValue::from_value(value)? },
+        };
+    });
+    let constants = columns.iter().map(|column| {
+        let constant = format_ident!("{}", column.ident.to_string().trim_start_matches("r#").to_uppercase());
+        let name = &column.name;
+
+        quote! { pub const #constant: &'static str = #name; }
+    });
+    let key_type = options.key_type.as_ref().map(|key_type| quote! { const KEY_TYPE: ::flyer_orm::KeyType = ::flyer_orm::KeyType::#key_type; });
+    let timestamps = options.timestamps.then(|| quote! { const TIMESTAMPS: bool = true; });
+    let created_at = options.created_at.as_ref().map(|column| quote! { const CREATED_AT: &'static str = #column; });
+    let updated_at = options.updated_at.as_ref().map(|column| quote! { const UPDATED_AT: &'static str = #column; });
+    let soft_deletes = options.soft_deletes.then(|| quote! { const SOFT_DELETES: bool = true; });
+    let deleted_at = options.deleted_at.as_ref().map(|column| quote! { const DELETED_AT: &'static str = #column; });
+    let morph_name = options.morph_name.as_ref().map(|name| quote! { const MORPH_NAME: &'static str = #name; });
+    let versions = fields.iter().filter(|field| field.version).collect::<Vec<_>>();
+
+    if versions.len() > 1 {
+        return Err(Error::new_spanned(&versions[1].ident, "a model has one `#[orm(version)]` field"));
+    }
+
+    let version = versions.first().map(|field| {
+        let name = field.column.as_ref().unwrap();
+
+        quote! { const VERSION: ::std::option::Option<&'static str> = ::std::option::Option::Some(#name); }
+    });
+
+    if let Some(name) = options.fillable.iter().chain(&options.guarded).find(|name| !names.contains(name)) {
+        return Err(Error::new_spanned(ident, format!("no column `{}` to fill or guard", name)));
+    }
+
+    let fillable = (!options.fillable.is_empty()).then(|| {
+        let fillable = &options.fillable;
+
+        quote! { const FILLABLE: &'static [&'static str] = &[#(#fillable),*]; }
+    });
+    let guarded = (!options.guarded.is_empty()).then(|| {
+        let guarded = &options.guarded;
+
+        quote! { const GUARDED: &'static [&'static str] = &[#(#guarded),*]; }
+    });
+    let reject_guarded = options.reject_guarded.then(|| quote! { const REJECT_GUARDED: bool = true; });
+    let from_values = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let from_column = match &field.cast {
+            Some(cast) => quote! { ::flyer_orm::cast::from_column::<#cast, _> },
+            None => quote! { ::flyer_orm::value::from_column },
+        };
+
+        return match &field.column {
+            Some(name) if field.default || field.computed => quote! {
+                #ident: match values.contains_key(#name) {
+                    true => #from_column(&mut values, #name)?,
+                    false => ::std::default::Default::default(),
+                }
+            },
+            Some(name) => quote! { #ident: #from_column(&mut values, #name)? },
+            None => quote! { #ident: ::std::default::Default::default() },
+        };
+    });
+    let eager_load = eager_load(&fields)?;
+    let relation = relation(&fields)?;
+    let (visibility, serialize) = serialize(&input, &options, &fields)?;
+    let mut row_generics = input.generics.clone();
+
+    row_generics.params.insert(0, parse_quote!('r));
+    row_generics.params.push(parse_quote!(R: ::flyer_orm::sqlx::Row));
+    row_generics.make_where_clause().predicates.push(parse_quote!(R::Database: ::flyer_orm::Driver<Row = R>));
+
+    let (row_impl_generics, _, row_where_clause) = row_generics.split_for_impl();
+    let from_row = fields.iter().any(|field| field.cast.is_some()).then(|| quote! {
+        impl #row_impl_generics ::flyer_orm::sqlx::FromRow<'r, R> for #ident #type_generics #row_where_clause {
+            fn from_row(row: &'r R) -> ::std::result::Result<Self, ::flyer_orm::sqlx::Error> {
+                let values = ::flyer_orm::cast::from_row::<R::Database>(row)?;
+
+                return <Self as ::flyer_orm::Model>::from_values(values).map_err(|e| ::flyer_orm::sqlx::Error::Decode(e.into()));
+            }
+        }
+    });
+    let snapshot = original.map(|field| quote! {
+        fn snapshot(&self) -> ::std::option::Option<&::flyer_orm::Original> {
+            return ::std::option::Option::Some(&self.#field);
+        }
+
+        fn snapshot_mut(&mut self) -> ::std::option::Option<&mut ::flyer_orm::Original> {
+            return ::std::option::Option::Some(&mut self.#field);
+        }
+    });
+
+    return Ok(quote! {
+        impl #impl_generics ::flyer_orm::Model for #ident #type_generics #where_clause {
+            const TABLE: &'static str = #table;
+            const PRIMARY_KEY: &'static str = #primary_key;
+            const COLUMNS: &'static [&'static str] = &[#(#names),*];
+            #key_type
+            #timestamps
+            #created_at
+            #updated_at
+            #soft_deletes
+            #deleted_at
+            #morph_name
+            #fillable
+            #guarded
+            #reject_guarded
+            #version
+            #blind_indexes
+
+            fn values(&self) -> ::flyer_orm::anyhow::Result<::std::vec::Vec<(&'static str, ::flyer_orm::Value)>> {
+                return ::std::result::Result::Ok(::std::vec![#((#names, #encode)),*]);
+            }
+
+            fn from_values(values: ::std::vec::Vec<(::std::string::String, ::flyer_orm::Value)>) -> ::flyer_orm::anyhow::Result<Self> {
+                let mut values = values.into_iter().collect::<::std::collections::HashMap<_, _>>();
+
+                return ::std::result::Result::Ok(Self {
+                    #(#from_values),*
+                });
+            }
+
+            fn set_value(&mut self, column: &str, value: ::flyer_orm::Value) -> ::flyer_orm::anyhow::Result<()> {
+                match column {
+                    #(#names => self.#idents = #decode,)*
+                    _ => return ::std::result::Result::Err(::flyer_orm::anyhow::anyhow!("`{}` has no column `{}`", #table, column)),
+                }
+
+                return ::std::result::Result::Ok(());
+            }
+
+            #cast_values
+
+            #eager_load
+
+            #relation
+
+            #snapshot
+
+            #visibility
+        }
+
+        #serialize
+
+        #from_row
+
+        impl #impl_generics #ident #type_generics #where_clause {
+            #(#constants)*
+        }
+    });
+}
+
+fn options(input: &DeriveInput) -> Result<Options> {
+    let mut options = Options {
+        table: format!("{}s", snake_case(&input.ident.to_string())),
+        primary_key: "id".to_string(),
+        key_type: None,
+        timestamps: false,
+        created_at: None,
+        updated_at: None,
+        soft_deletes: false,
+        deleted_at: None,
+        morph_name: None,
+        hidden: Vec::new(),
+        visible: Vec::new(),
+        fillable: Vec::new(),
+        guarded: Vec::new(),
+        reject_guarded: false,
+    };
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("orm")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                options.table = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("primary_key") {
+                options.primary_key = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("key_type") {
+                let name = meta.value()?.parse::<LitStr>()?;
+
+                options.key_type = Some(match name.value().as_str() {
+                    "increment" => format_ident!("Increment"),
+                    "uuid" => format_ident!("Uuid"),
+                    "uuid_v7" => format_ident!("UuidV7"),
+                    "ulid" => format_ident!("Ulid"),
+                    _ => return Err(Error::new_spanned(name, "key_type is one of `increment`, `uuid`, `uuid_v7` or `ulid`")),
+                });
+            } else if meta.path.is_ident("timestamps") {
+                options.timestamps = true;
+            } else if meta.path.is_ident("created_at") {
+                options.timestamps = true;
+                options.created_at = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("updated_at") {
+                options.timestamps = true;
+                options.updated_at = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("soft_deletes") {
+                options.soft_deletes = true;
+            } else if meta.path.is_ident("deleted_at") {
+                options.soft_deletes = true;
+                options.deleted_at = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("morph_name") {
+                options.morph_name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("hidden") {
+                options.hidden.extend(names(&meta)?);
+            } else if meta.path.is_ident("visible") {
+                options.visible.extend(names(&meta)?);
+            } else if meta.path.is_ident("fillable") {
+                options.fillable.extend(names(&meta)?);
+            } else if meta.path.is_ident("guarded") {
+                options.guarded.extend(names(&meta)?);
+            } else if meta.path.is_ident("reject_guarded") {
+                options.reject_guarded = true;
+            } else {
+                return Err(meta.error("unknown orm attribute"));
+            }
+
+            return Ok(());
+        })?;
+    }
+
+    return Ok(options);
+}
+
+/// Named fields, honouring `#[sqlx(rename = "...")]` and `#[sqlx(skip)]` so the columns match `FromRow`.
+fn fields(input: &DeriveInput) -> Result<Vec<Field>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(input, "Model can only be derived for structs"));
+    };
+
+    let Fields::Named(named) = &data.fields else {
+        return Err(Error::new_spanned(input, "Model can only be derived for structs with named fields"));
+    };
+
+    let mut fields = Vec::new();
+
+    for field in &named.named {
+        let ident = field.ident.clone().unwrap();
+        let mut name = ident.to_string().trim_start_matches("r#").to_string();
+        let mut skip = false;
+        let mut default = false;
+        let mut computed = false;
+        let mut relation = None;
+        let mut serialize_as = None;
+        let mut cast = None;
+        let mut encrypted = false;
+        let mut blind_index = None;
+        let mut version = false;
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("sqlx")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("default") {
+                    default = true;
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                }
+
+                return Ok(());
+            })?;
+        }
+
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("orm")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("computed") {
+                    computed = true;
+                } else if meta.path.is_ident("cast") {
+                    let name = meta.value()?.parse::<LitStr>()?;
+
+                    cast = Some(match name.value().as_str() {
+                        "json" => syn::parse_quote! { ::flyer_orm::cast::Json },
+                        "enum" => syn::parse_quote! { ::flyer_orm::cast::Enum },
+                        "bool" => syn::parse_quote! { ::flyer_orm::cast::Bool },
+                        "datetime" => syn::parse_quote! { ::flyer_orm::cast::DateTime },
+                        "comma" => syn::parse_quote! { ::flyer_orm::cast::Comma },
+                        _ => name.parse::<syn::Path>()?,
+                    });
+                } else if meta.path.is_ident("encrypted") {
+                    cast = Some(syn::parse_quote! { ::flyer_orm::encryption::Encrypted });
+                    encrypted = true;
+                } else if meta.path.is_ident("blind_index") {
+                    blind_index = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("version") {
+                    version = true;
+                } else if meta.path.is_ident("serialize_as") {
+                    serialize_as = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("has_many") {
+                    relation = Some(Relation::HasMany(meta.value()?.parse::<LitStr>()?.value()));
+                } else if meta.path.is_ident("has_one") {
+                    relation = Some(Relation::HasOne(meta.value()?.parse::<LitStr>()?.value()));
+                } else if meta.path.is_ident("belongs_to") {
+                    relation = Some(Relation::BelongsTo(meta.value()?.parse::<LitStr>()?.value()));
+                } else if meta.path.is_ident("morph_many") {
+                    relation = Some(Relation::MorphMany(meta.value()?.parse::<LitStr>()?.value()));
+                } else if meta.path.is_ident("morph_one") {
+                    relation = Some(Relation::MorphOne(meta.value()?.parse::<LitStr>()?.value()));
+                } else if meta.path.is_ident("morph_to") {
+                    relation = Some(Relation::MorphTo);
+                } else if meta.path.is_ident("belongs_to_many") {
+                    let (mut pivot, mut foreign_key, mut related_key) = (None, None, None);
+
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("pivot") {
+                            pivot = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("foreign_key") {
+                            foreign_key = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("related_key") {
+                            related_key = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else {
+                            return Err(meta.error("unknown belongs_to_many attribute"));
+                        }
+
+                        return Ok(());
+                    })?;
+
+                    let (Some(pivot), Some(foreign_key), Some(related_key)) = (pivot, foreign_key, related_key) else {
+                        return Err(meta.error("belongs_to_many needs `pivot`, `foreign_key` and `related_key`"));
+                    };
+
+                    relation = Some(Relation::BelongsToMany { pivot: pivot, foreign_key: foreign_key, related_key: related_key });
+                } else {
+                    return Err(meta.error("unknown orm field attribute"));
+                }
+
+                return Ok(());
+            })?;
+        }
+
+        let column = !skip && !is_original(&field.ty) && relation.is_none();
+
+        if blind_index.is_some() && !encrypted {
+            return Err(Error::new_spanned(&ident, "`blind_index` needs `#[orm(encrypted)]`"));
+        }
+
+        if version && (!column || computed) {
+            return Err(Error::new_spanned(&ident, "`version` needs a column of the table"));
+        }
+
+        fields.push(Field {
+            ident: ident,
+            ty: field.ty.clone(),
+            column: if column { Some(name) } else { None },
+            default: default,
+            computed: computed,
+            relation: relation,
+            serialize_as: serialize_as,
+            cast: cast,
+            encrypted: encrypted,
+            blind_index: blind_index,
+            version: version,
+        });
+    }
+
+    return Ok(fields);
+}
+
+/// The string list of `#[orm(hidden("a", "b"))]`.
+fn names(meta: &syn::meta::ParseNestedMeta) -> Result<Vec<String>> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+
+    return Ok(Punctuated::<LitStr, syn::Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .map(|name| name.value())
+        .collect());
+}
+
+/// `HIDDEN`, `VISIBLE` and a `Serialize` impl writing the fields `Model::serialize_visible` allows,
+/// only when the model has field lists or `serialize_as` renames.
+fn serialize(input: &DeriveInput, options: &Options, fields: &[Field]) -> Result<(Option<TokenStream>, Option<TokenStream>)> {
+    if options.hidden.is_empty() && options.visible.is_empty() && fields.iter().all(|field| field.serialize_as.is_none()) {
+        return Ok((None, None));
+    }
+
+    // The `Serialize` impl below replaces serde's derive, which would conflict with it and whose field attributes it can't honour.
+    let field_attrs = match &input.data {
+        Data::Struct(data) => data.fields.iter().flat_map(|field| &field.attrs).collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let serde = input.attrs.iter().chain(field_attrs).find(|attr| attr.path().is_ident("serde"));
+
+    if let Some(attr) = serde {
+        return Err(Error::new_spanned(attr, "`#[orm(hidden, visible, serialize_as)]` implement `Serialize`, which ignores `#[serde(...)]` \
+            and conflicts with `#[derive(Serialize)]`, rename fields with `#[orm(serialize_as = \"...\")]` instead"));
+    }
+
+    for name in options.hidden.iter().chain(&options.visible) {
+        if !fields.iter().any(|field| field.ident.to_string().trim_start_matches("r#") == name) {
+            return Err(Error::new_spanned(&input.ident, format!("no field `{}` to hide or show", name)));
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let hidden = &options.hidden;
+    let visible = &options.visible;
+    let entries = fields.iter()
+        .filter(|field| !is_original(&field.ty) && !matches!(field.relation, Some(Relation::MorphTo)))
+        .map(|field| {
+            let ident = &field.ident;
+            let name = ident.to_string().trim_start_matches("r#").to_string();
+            let key = field.serialize_as.clone().unwrap_or_else(|| name.clone());
+
+            quote! {
+                if visibility.shows(#name, Self::HIDDEN, Self::VISIBLE) {
+                    ::flyer_orm::serde::ser::SerializeMap::serialize_entry(&mut map, #key, &self.#ident)?;
+                }
+            }
+        });
+
+    let model = quote! {
+        const HIDDEN: &'static [&'static str] = &[#(#hidden),*];
+        const VISIBLE: &'static [&'static str] = &[#(#visible),*];
+
+        fn serialize_visible<S: ::flyer_orm::serde::Serializer>(&self, serializer: S, visibility: &::flyer_orm::serialize::Visibility) -> ::std::result::Result<S::Ok, S::Error>
+        where
+            Self: ::flyer_orm::serde::Serialize
+        {
+            let mut map = ::flyer_orm::serde::Serializer::serialize_map(serializer, ::std::option::Option::None)?;
+
+            #(#entries)*
+
+            return ::flyer_orm::serde::ser::SerializeMap::end(map);
+        }
+    };
+
+    let serialize = quote! {
+        impl #impl_generics ::flyer_orm::serde::Serialize for #ident #type_generics #where_clause {
+            fn serialize<S: ::flyer_orm::serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+                return ::flyer_orm::Model::serialize_visible(self, serializer, &::std::default::Default::default());
+            }
+        }
+    };
+
+    return Ok((Some(model), Some(serialize)));
+}
+
+/// `Model::eager_load` for the relation fields, `Vec<R>` for many and `Option<R>` for one related model.
+fn eager_load(fields: &[Field]) -> Result<Option<TokenStream>> {
+    let mut arms = Vec::new();
+
+    for field in fields {
+        let Some(relation) = &field.relation else {
+            continue;
+        };
+
+        let ident = &field.ident;
+        let name = ident.to_string().trim_start_matches("r#").to_string();
+        let set = quote! { |model, related| model.#ident = related };
+
+        if let Relation::MorphTo = relation {
+            arms.push(quote! {
+                #name => ::flyer_orm::relation::load_morph_to::<E, Self, _>(db, models, #name, eager, #set).await,
+            });
+
+            continue;
+        }
+
+        let related = related(&field.ty)?;
+
+        arms.push(match relation {
+            Relation::HasMany(foreign_key) => quote! {
+                #name => ::flyer_orm::relation::load_has_many::<E, Self, #related, _>(db, models, #foreign_key, eager, #set).await,
+            },
+            Relation::HasOne(foreign_key) => quote! {
+                #name => ::flyer_orm::relation::load_has_one::<E, Self, #related, _>(db, models, #foreign_key, eager, #set).await,
+            },
+            Relation::BelongsTo(foreign_key) => quote! {
+                #name => ::flyer_orm::relation::load_belongs_to::<E, Self, #related, _>(db, models, #foreign_key, eager, #set).await,
+            },
+            Relation::BelongsToMany { pivot, foreign_key, related_key } => quote! {
+                #name => ::flyer_orm::relation::load_belongs_to_many::<E, Self, #related, _>(db, models, #pivot, #foreign_key, #related_key, eager, #set).await,
+            },
+            Relation::MorphMany(morph) => quote! {
+                #name => ::flyer_orm::relation::load_morph_many::<E, Self, #related, _>(db, models, #morph, eager, #set).await,
+            },
+            Relation::MorphOne(morph) => quote! {
+                #name => ::flyer_orm::relation::load_morph_one::<E, Self, #related, _>(db, models, #morph, eager, #set).await,
+            },
+            Relation::MorphTo => unreachable!(),
+        });
+    }
+
+    if arms.is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(quote! {
+        fn eager_load<'a, E: ::flyer_orm::Executor>(db: &'a E, models: &'a mut [Self], eager: &'a ::flyer_orm::Eager) -> ::flyer_orm::futures::future::BoxFuture<'a, ::flyer_orm::anyhow::Result<()>> {
+            return ::std::boxed::Box::pin(async move {
+                return match eager.name.as_str() {
+                    #(#arms)*
+                    _ => ::flyer_orm::relation::unknown::<Self>(eager),
+                };
+            });
+        }
+    }));
+}
+
+/// `Model::relation` for the relation fields.
+fn relation(fields: &[Field]) -> Result<Option<TokenStream>> {
+    let mut arms = Vec::new();
+
+    for field in fields {
+        let Some(relation) = &field.relation else {
+            continue;
+        };
+
+        let name = field.ident.to_string().trim_start_matches("r#").to_string();
+
+        if let Relation::MorphTo = relation {
+            arms.push(quote! {
+                #name => ::std::option::Option::Some(::flyer_orm::relation::Relation::MorphTo { name: #name }),
+            });
+
+            continue;
+        }
+
+        let related = related(&field.ty)?;
+        let table = quote! { <#related as ::flyer_orm::Model>::TABLE };
+
+        arms.push(match relation {
+            Relation::HasMany(foreign_key) => quote! {
+                #name => ::std::option::Option::Some(::flyer_orm::relation::Relation::HasMany { table: #table, foreign_key: #foreign_key }),
+            },
+            Relation::HasOne(foreign_key) => quote! {
+                #name => ::std::option::Option::Some(::flyer_orm::relation::Relation::HasOne { table: #table, foreign_key: #foreign_key }),
+            },
+            Relation::BelongsTo(foreign_key) => quote! {
+                #name => ::std::option::Option::Some(::flyer_orm::relation::Relation::BelongsTo { table: #table, foreign_key: #foreign_key }),
+            },
+            Relation::BelongsToMany { pivot, foreign_key, related_key } => quote! {
+                #name => ::std::option::Option::Some(::flyer_orm::relation::Relation::BelongsToMany { table: #table, pivot: #pivot, foreign_pivot_key: #foreign_key, related_pivot_key: #related_key }),
+            },
+            Relation::MorphMany(morph) => quote! {
+                #name => ::std::option::Option::Some(::flyer_orm::relation::Relation::MorphMany { table: #table, name: #morph }),
+            },
+            Relation::MorphOne(morph) => quote! {
+                #name => ::std::option::Option::Some(::flyer_orm::relation::Relation::MorphOne { table: #table, name: #morph }),
+            },
+            Relation::MorphTo => unreachable!(),
+        });
+    }
+
+    if arms.is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(quote! {
+        fn relation(name: &str) -> ::std::option::Option<::flyer_orm::relation::Relation> {
+            return match name {
+                #(#arms)*
+                _ => ::std::option::Option::None,
+            };
+        }
+    }));
+}
+
+/// The `R` of a `Vec<R>` or `Option<R>` relation field.
+fn related(ty: &syn::Type) -> Result<&syn::Type> {
+    if let syn::Type::Path(path) = ty
+        && let Some(syn::PathArguments::AngleBracketed(arguments)) = path.path.segments.last().map(|segment| &segment.arguments)
+        && let Some(syn::GenericArgument::Type(related)) = arguments.args.first()
+    {
+        return Ok(related);
+    }
+
+    return Err(Error::new_spanned(ty, "relation fields must be a `Vec<Model>` or `Option<Model>`"));
+}
+
+/// The field holding the `Original` values used for dirty tracking.
+fn original(input: &DeriveInput) -> Option<syn::Ident> {
+    let Data::Struct(data) = &input.data else {
+        return None;
+    };
+
+    return data.fields.iter()
+        .find(|field| is_original(&field.ty))
+        .and_then(|field| field.ident.clone());
+}
+
+fn is_original(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else {
+        return false;
+    };
+
+    return path.path.segments.last().is_some_and(|segment| segment.ident == "Original");
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            snake.push('_');
+        }
+
+        snake.extend(c.to_lowercase());
+    }
+
+    return snake;
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{Driver, Value, query::Values, value::FromValue};
+
+/// Converts a field from and to its column value, set on a field with `#[orm(cast = "...")]`.
+///
+/// The built-in casts are `json`, `enum`, `bool`, `datetime` and `comma`, and `#[orm(encrypted)]` for
+/// `encryption::Encrypted`. Any other name is the path of a type implementing `Cast` for the field's type.
+///
+/// ```ignore
+/// struct Cents;
+///
+/// impl Cast<f64> for Cents {
+///     fn decode(value: Value) -> Result<f64> {
+///         return Ok(i64::from_value(value)? as f64 / 100.0);
+///     }
+///
+///     fn encode(value: &f64) -> Result<Value> {
+///         return Ok(Value::Int((value * 100.0).round() as i64));
+///     }
+/// }
+/// ```
+pub trait Cast<T> {
+    fn decode(value: Value) -> Result<T>;
+
+    fn encode(value: &T) -> Result<Value>;
+}
+
+/// JSON text, or a JSON column, to a `serde_json::Value` or any `Deserialize` struct.
+///
+/// Text that isn't JSON fails to decode, a string is the quoted JSON string, like `"\"draft\""`.
+pub struct Json;
+
+impl <T: Serialize + DeserializeOwned>Cast<T> for Json {
+    fn decode(value: Value) -> Result<T> {
+        return Ok(match value {
+            Value::Text(text) => serde_json::from_str(&text).map_err(|e| anyhow!("invalid JSON: {}", e))?,
+            Value::Bytes(bytes) => serde_json::from_slice(&bytes)?,
+            value => serde_json::from_value(value.into())?,
+        });
+    }
+
+    fn encode(value: &T) -> Result<Value> {
+        return Ok(match serde_json::to_value(value)? {
+            serde_json::Value::Null => Value::Null,
+            value => Value::Json(value),
+        });
+    }
+}
+
+/// An integer or text column to an enum, through its `Serialize` and `Deserialize` impls.
+///
+/// Text columns match the variant names, use `serde_repr` for enums stored as integers.
+pub struct Enum;
+
+impl <T: Serialize + DeserializeOwned>Cast<T> for Enum {
+    fn decode(value: Value) -> Result<T> {
+        return Ok(serde_json::from_value(value.into())?);
+    }
+
+    fn encode(value: &T) -> Result<Value> {
+        return Ok(Value::from(serde_json::to_value(value)?));
+    }
+}
+
+/// `0`/`1`, and text like `"true"`/`"false"` or `"yes"`/`"no"`, to a `bool` or `Option<bool>`, other text fails to decode.
+pub struct Bool;
+
+impl <T: FromValue + Into<Value> + Clone>Cast<T> for Bool {
+    fn decode(value: Value) -> Result<T> {
+        return T::from_value(match value {
+            Value::Int(value) => Value::Bool(value != 0),
+            Value::Text(text) => match text.to_lowercase().as_str() {
+                "1" | "t" | "true" | "y" | "yes" => Value::Bool(true),
+                "0" | "f" | "false" | "n" | "no" => Value::Bool(false),
+                _ => return Err(anyhow!("expected a boolean, got {:?}", text)),
+            },
+            value => value,
+        });
+    }
+
+    fn encode(value: &T) -> Result<Value> {
+        return Ok(value.clone().into());
+    }
+}
+
+/// Text or unix timestamp columns, like the `created_at` of SQLite, to a chrono or `time` type.
+///
+/// The conversion is the `FromValue` impl of the field's type. What the cast adds is that a model with casts is read
+/// through `FromValue` by the `FromRow` of `#[derive(Model)]`, where sqlx's `Decode` only reads date columns.
+pub struct DateTime;
+
+impl <T: FromValue + Into<Value> + Clone>Cast<T> for DateTime {
+    fn decode(value: Value) -> Result<T> {
+        return T::from_value(value);
+    }
+
+    fn encode(value: &T) -> Result<Value> {
+        return Ok(value.clone().into());
+    }
+}
+
+/// A comma separated text column to a `Vec<String>`, `NULL` and empty text are an empty list.
+///
+/// A JSON array of strings, like a list given to `create`, is read as the list.
+pub struct Comma;
+
+impl Cast<Vec<String>> for Comma {
+    fn decode(value: Value) -> Result<Vec<String>> {
+        return match value {
+            Value::Null => Ok(Vec::new()),
+            Value::Text(text) if text.is_empty() => Ok(Vec::new()),
+            Value::Text(text) => Ok(text.split(',').map(str::to_string).collect()),
+            Value::Json(value @ serde_json::Value::Array(_)) => Ok(serde_json::from_value(value)?),
+            value => Err(anyhow!("expected comma separated text, got {:?}", value)),
+        };
+    }
+
+    fn encode(value: &Vec<String>) -> Result<Value> {
+        return Ok(Value::Text(value.join(",")));
+    }
+}
+
+/// Takes a column out of a row read into `Value`s through a cast, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub fn from_column<C: Cast<T>, T>(values: &mut HashMap<String, Value>, column: &str) -> Result<T> {
+    return C::decode(values.remove(column).unwrap_or(Value::Null)).map_err(|e| anyhow!("column `{}`: {}", column, e));
+}
+
+/// The columns of a row as `Value`s, for the `FromRow` impl `#[derive(Model)]` writes for models with casts.
+#[doc(hidden)]
+pub fn from_row<DB: Driver>(row: &DB::Row) -> std::result::Result<Vec<(String, Value)>, sqlx::Error> {
+    return Ok(<Values<DB> as sqlx::FromRow<'_, DB::Row>>::from_row(row)?.values);
+}
+
+/// A column value run through its cast, as it would be written by `Model::values`, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub fn recast<C: Cast<T>, T>(column: &str, value: Value) -> Result<Value> {
+    return C::decode(value).and_then(|value| C::encode(&value)).map_err(|e| anyhow!("column `{}`: {}", column, e));
+}
@@ -0,0 +1,140 @@
+use std::sync::{LazyLock, RwLock};
+
+use aes_gcm_siv::{Aes256GcmSiv, AeadCore, Key, KeyInit, Nonce, aead::{Aead, OsRng}};
+use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{Value, cast::Cast};
+
+/// Prefix of encrypted values, text without it is read as plain text, see `decrypt`.
+const PREFIX: &str = "enc:";
+
+static KEYS: LazyLock<RwLock<Keys>> = LazyLock::new(|| RwLock::new(Keys::default()));
+
+#[derive(Default)]
+struct Keys {
+    keys: Vec<(String, [u8; 32])>,
+    index: Option<[u8; 32]>,
+}
+
+/// Sets the AES-256-GCM-SIV keys of `#[orm(encrypted)]` columns by id, the first encrypts and all of them decrypt.
+///
+/// Values are stored as `enc:{id}:{nonce and ciphertext}`, so rotating a key is putting the new key first and keeping
+/// the old ones to read the rows written with them. A column is encrypted with the new key the next time it is written.
+///
+/// ```ignore
+/// encryption::set_keys(&[("2025", new_key), ("2024", old_key)]);
+/// encryption::set_index_key(index_key);
+/// ```
+pub fn set_keys(keys: &[(&str, [u8; 32])]) {
+    KEYS.write().unwrap().keys = keys.iter().map(|(id, key)| (id.to_string(), *key)).collect();
+}
+
+/// Sets the HMAC key of blind indexes, it is kept when the encryption keys rotate so the indexes stay valid.
+pub fn set_index_key(key: [u8; 32]) {
+    KEYS.write().unwrap().index = Some(key);
+}
+
+/// Encrypts with the current key and a random nonce, so equal texts encrypt differently.
+pub fn encrypt(text: &str) -> Result<String> {
+    let keys = KEYS.read().unwrap();
+    let Some((id, key)) = keys.keys.first() else {
+        return Err(anyhow!("no encryption keys, set them with `encryption::set_keys`"));
+    };
+
+    let nonce = Aes256GcmSiv::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256GcmSiv::new(Key::<Aes256GcmSiv>::from_slice(key))
+        .encrypt(&nonce, text.as_bytes())
+        .map_err(|_| anyhow!("could not encrypt value"))?;
+
+    return Ok(format!("{}{}:{}", PREFIX, id, URL_SAFE_NO_PAD.encode([nonce.as_slice(), &ciphertext].concat())));
+}
+
+/// Decrypts with the key the value was encrypted with, text without the `enc:` prefix is returned as is.
+///
+/// The plain text is what lets a column be encrypted after it has rows: they are read as they are and encrypted
+/// the next time the column is written. It is also the plain text a model is created with. Such text is not
+/// authenticated, so anyone able to write the column can put plain text in it, re-save the rows to encrypt them all.
+pub fn decrypt(text: &str) -> Result<String> {
+    let Some((id, data)) = text.strip_prefix(PREFIX).and_then(|text| text.split_once(':')) else {
+        return Ok(text.to_string());
+    };
+
+    let keys = KEYS.read().unwrap();
+    let Some((_, key)) = keys.keys.iter().find(|(key, _)| key == id) else {
+        return Err(anyhow!("no encryption key `{}`", id));
+    };
+
+    let data = URL_SAFE_NO_PAD.decode(data)?;
+
+    if data.len() < 12 {
+        return Err(anyhow!("encrypted value is too short"));
+    }
+
+    let (nonce, ciphertext) = data.split_at(12);
+    let text = Aes256GcmSiv::new(Key::<Aes256GcmSiv>::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("could not decrypt value with key `{}`", id))?;
+
+    return Ok(String::from_utf8(text)?);
+}
+
+/// The blind index of a text, a HMAC under the index key that `where` compares instead of the encrypted column.
+pub fn blind_index(text: &str) -> Result<String> {
+    let Some(key) = KEYS.read().unwrap().index else {
+        return Err(anyhow!("no blind index key, set it with `encryption::set_index_key`"));
+    };
+
+    return Ok(URL_SAFE_NO_PAD.encode(sign(&key, text)?));
+}
+
+/// The blind index column value of an encrypted or plain text column value, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub fn index(value: &Value) -> Result<Value> {
+    return match value {
+        Value::Text(text) => Ok(Value::Text(blind_index(&decrypt(text)?)?)),
+        _ => Ok(Value::Null),
+    };
+}
+
+fn sign(key: &[u8], text: &str) -> Result<Vec<u8>> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)?;
+
+    mac.update(text.as_bytes());
+
+    return Ok(mac.finalize().into_bytes().to_vec());
+}
+
+/// The cast of `#[orm(encrypted)]` fields, a `String` or `Option<String>` stored encrypted.
+pub struct Encrypted;
+
+impl Cast<String> for Encrypted {
+    fn decode(value: Value) -> Result<String> {
+        return match value {
+            Value::Text(text) => decrypt(&text),
+            value => Err(anyhow!("expected encrypted text, got {:?}", value)),
+        };
+    }
+
+    fn encode(value: &String) -> Result<Value> {
+        return Ok(Value::Text(encrypt(value)?));
+    }
+}
+
+impl Cast<Option<String>> for Encrypted {
+    fn decode(value: Value) -> Result<Option<String>> {
+        return match value {
+            Value::Null => Ok(None),
+            value => Ok(Some(<Self as Cast<String>>::decode(value)?)),
+        };
+    }
+
+    fn encode(value: &Option<String>) -> Result<Value> {
+        return match value {
+            Some(value) => <Self as Cast<String>>::encode(value),
+            None => Ok(Value::Null),
+        };
+    }
+}
@@ -1,38 +1,106 @@
+#![allow(clippy::needless_return, clippy::redundant_field_names, clippy::len_zero, clippy::needless_lifetimes, clippy::type_complexity)]
+
 pub mod sqlite;
 pub mod postgres;
 pub mod mysql;
 pub mod query;
+pub mod value;
+pub mod model;
+pub mod relation;
+pub mod observer;
+pub mod serialize;
+pub mod cast;
+pub mod encryption;
+
+use std::{collections::HashMap, marker::PhantomData, str, sync::{Arc, LazyLock}};
+
+use anyhow::Result;
+use futures::stream::BoxStream;
+use sqlx::{Encode, FromRow, Pool, types::Type};
+
+use crate::model::Guard;
+use crate::query::{Cursor, CursorPagination, JoinQuery, JoinType, Keyed, Lock, LockMode, LockWait, Order, OrderQuery, Pagination, QueryPosition, Statement, Transaction, Trashed, WhereQuery, WhereQueryGroup};
+
+pub use crate::value::{IntoValues, Value};
+pub use crate::model::{Decodable, KeyType, MassAssignment, Model, ModelNotFound, Original, Scope, StaleModel};
+pub use crate::relation::{Eager, Morphed};
+pub use crate::observer::{Attributes, Observer};
+pub use crate::serialize::Serialized;
+pub use flyer_orm_macros::Model;
+
+#[doc(hidden)]
+pub use anyhow;
+#[doc(hidden)]
+pub use futures;
+#[doc(hidden)]
+pub use serde;
+#[doc(hidden)]
+pub use sqlx;
+
+pub(crate) static mut CONNECTIONS: LazyLock<HashMap<&str, String>> = LazyLock::new(HashMap::new);
+
+/// Binds dynamic [`Value`]s for a database, implemented next to each driver's `Encode` impl.
+///
+/// `Argument` is always `Value`, naming it as an associated type lets generic code rely on its `Encode` impl.
+pub trait Driver: sqlx::Database {
+    type Argument: for<'q> Encode<'q, Self> + Type<Self> + From<Value> + Send + 'static;
+
+    /// A bound value encoded up front, so a statement can pass its values in the order of its SQL.
+    type Encoded<'q>: Encode<'q, Self> + Type<Self> + Clone + Send + 'q;
+
+    /// Whether `insert_as` reads the row back with `RETURNING *`, instead of by its key.
+    const RETURNING: bool = false;
+
+    fn encode<'q, T: 'q + Encode<'q, Self> + Type<Self>>(value: T) -> Result<Self::Encoded<'q>>;
+
+    fn bind<'q>(arguments: &mut query::Arguments<'q, Self>, value: Value) -> Result<()> {
+        return arguments.add(Self::Argument::from(value));
+    }
 
-use std::{collections::HashMap, marker::PhantomData, str, sync::LazyLock};
+    /// Reads a column of a row as a [`Value`].
+    fn get(row: &<Self as sqlx::Database>::Row, column: &str) -> Result<Value>;
 
-use anyhow::{Ok, Result};
-use sqlx::{Arguments, Encode, FromRow, Pool, types::Type};
+    /// The text a string argument is bound as, `None` for other types. Used to hash values compared with blind indexed columns.
+    fn text<'q, T: Encode<'q, Self> + Type<Self>>(value: &T) -> Option<String>;
 
-use crate::query::{JoinQuery, JoinType, Order, OrderQuery, Pagination, Statement, Transaction, WhereQuery, WhereQueryGroup};
+    /// The clause a select ends with to lock its rows, `None` when the database has no row locks.
+    fn lock(lock: &Lock) -> Option<String>;
 
-pub(crate) static mut CONNECTIONS: LazyLock<HashMap<&str, String>> = LazyLock::new(|| HashMap::new());
+    /// Decodes a row with the model's `FromRow`, for generic code that can only name `Decodable`.
+    fn decode<M: Decodable>(row: &<Self as sqlx::Database>::Row) -> Result<M, sqlx::Error>;
+}
 
 #[allow(async_fn_in_trait)]
-pub trait Executor {
-    type T: sqlx::Database;
+pub trait Executor: Send + Sync + 'static {
+    type T: Driver;
 
     async fn new(url: &str) -> Self where Self: Sized;
 
     fn db<'q>(&'q self) -> &'q Pool<Self::T>; 
 
-    fn to_sql<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<String>;
+    /// A copy of the executor running its queries in a new transaction, or in a savepoint of the current one when it already has one.
+    async fn begin(&self) -> Result<Self> where Self: Sized;
+
+    /// Commits the transaction started with `begin`, or releases the savepoint of a nested one.
+    async fn commit(&self) -> Result<()>;
+
+    /// Rolls back the transaction started with `begin`, or to the savepoint of a nested one.
+    async fn rollback(&self) -> Result<()>;
+
+    fn to_sql<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<String>;
 
     async fn execute<'q>(&self, sql: &'q str) -> Result<()>;
 
-    async fn insert<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()>;
+    async fn insert<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<()>;
 
-    async fn update<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()>;
+    /// Returns the number of rows the update affected.
+    async fn update<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<u64>;
 
-    async fn count<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<u64>;
+    async fn count<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<u64>;
 
-    async fn delete<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()>;
+    async fn delete<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<()>;
 
-    async fn insert_as<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<O>
+    async fn insert_as<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<O>
     where
         O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized;
 
@@ -44,17 +112,22 @@ pub trait Executor {
     where
         O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized;
 
-    async fn all<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<Vec<O>>
+    /// The future is `Send` so generic code, like eager loading, can box it.
+    fn all<'q, O>(&self, statement: &Statement<'q, Self::T>) -> impl Future<Output = Result<Vec<O>>> + Send
     where
         O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized;
 
-    async fn first<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<O>
+    async fn first<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<O>
     where
         O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized;
 
-    async fn paginate<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<Pagination<O>>
+    async fn paginate<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<Pagination<O>>
     where
         O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized;
+
+    fn stream<'q, O>(&'q self, statement: &'q Statement<'q, Self::T>) -> BoxStream<'q, Result<O>>
+    where
+        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized + 'q;
 }
 
 pub struct DB;
@@ -92,12 +165,20 @@ impl <E: Executor>Database<E> {
         }
     }
 
-    pub async fn transaction<'q>(&self) -> Result<Transaction<'q, E::T>> {
-        return Ok(Transaction::new(self.executor.db().begin().await.unwrap()));
+    /// Starts a transaction, queries on the returned `Transaction` run in it until `commit` or `rollback`.
+    ///
+    /// `Transaction<E>` replaces the `Transaction<'t, DB>` wrapper of sqlx's transaction, which couldn't run queries,
+    /// build them with `transaction.query(..)` or pass `&transaction` where a `&Database<E>` is expected.
+    /// Started from a `Transaction` it is a savepoint, its `rollback` only undoes its own queries.
+    pub async fn transaction(&self) -> Result<Transaction<E>> {
+        return Ok(Transaction::new(Database {
+            executor: self.executor.begin().await?
+        }));
     }
 
+    /// Runs raw SQL without values, like a schema migration, statements may be separated by `;`.
     pub async fn execute(&self, sql: &str) -> Result<()> {
-        todo!();
+        return self.executor.execute(sql).await;
     }
 
     pub fn query<'q>(&'q self, table: &str) -> Query<'q, E> {
@@ -105,17 +186,34 @@ impl <E: Executor>Database<E> {
     }
 
     pub async fn close(&self) -> Result<()> {
-        return Ok(self.executor.db().close().await);
+        self.executor.db().close().await;
+
+        return Ok(());
     }
 }
 
-pub struct Query<'q, E: Executor> {
+/// A query on a table, `M` is the `Model` it was started from with `Model::query` or `()` for `Database::query`.
+pub struct Query<'q, E: Executor, M = ()> {
     db: &'q E,
     statement: Statement<'q, E::T>,
-    _marker: PhantomData<E>
+    timestamps: Option<(&'static str, &'static str)>,
+    touch: bool,
+    soft_deletes: Option<&'static str>,
+    guard: Option<Guard>,
+    blind_indexes: &'static [(&'static str, &'static str)],
+    generated_key: Option<(&'static str, KeyType)>,
+    /// The table and version column of a model with `Model::VERSION`.
+    version: Option<(&'static str, &'static str)>,
+    cast_values: Option<fn(Vec<(String, Value)>) -> Result<Vec<(String, Value)>>>,
+    trashed: Trashed,
+    scoped: bool,
+    scopes: Vec<(&'static str, Scope<E, M>)>,
+    eager: Vec<Eager>,
+    _marker: PhantomData<E>,
+    _model: PhantomData<M>
 }
 
-impl <'q, E>Query<'q, E>
+impl <'q, E, M>Query<'q, E, M>
 where
     E: Executor
 {
@@ -123,8 +221,157 @@ where
         return Self {
             db: exc,
             statement: Statement::<'q, E::T>::new(table),
+            timestamps: None,
+            touch: true,
+            soft_deletes: None,
+            guard: None,
+            blind_indexes: &[],
+            generated_key: None,
+            version: None,
+            cast_values: None,
+            trashed: Trashed::Without,
+            scoped: false,
+            scopes: Vec::new(),
+            eager: Vec::new(),
             _marker: PhantomData,
+            _model: PhantomData,
+        }
+    }
+
+    /// Pass `false` to leave the model's `created_at`/`updated_at` columns as they are.
+    pub fn touch(&mut self, touch: bool) -> &mut Self {
+        self.touch = touch;
+
+        return self;
+    }
+
+    /// Includes the soft deleted rows of the model.
+    pub fn with_trashed(&mut self) -> &mut Self {
+        self.trashed = Trashed::With;
+
+        return self;
+    }
+
+    /// Only the soft deleted rows of the model.
+    pub fn only_trashed(&mut self) -> &mut Self {
+        self.trashed = Trashed::Only;
+
+        return self;
+    }
+
+    /// Applies a local scope, a function adding reusable clauses to the query.
+    ///
+    /// The scope's where clauses are kept in their own parentheses and joined to the query's with `AND`.
+    pub fn scope<F: FnOnce(&mut Self)>(&mut self, scope: F) -> &mut Self {
+        let queries = std::mem::take(&mut self.statement.query.where_queries);
+
+        scope(self);
+
+        let scoped = std::mem::replace(&mut self.statement.query.where_queries, queries);
+
+        if scoped.len() != 0 {
+            self.statement.query.group_where();
+            self.statement.query.where_queries.push(WhereQuery {
+                column: None,
+                operator: None,
+                position: if self.statement.query.where_queries.len() == 0 { None } else { Some(QueryPosition::AND) },
+                group: Some(scoped),
+                values: None
+            });
         }
+
+        return self;
+    }
+
+    /// Leaves out the global scope added with `Model::add_global_scope` under `name`.
+    pub fn without_global_scope(&mut self, name: &str) -> &mut Self {
+        self.scopes.retain(|(scope, _)| *scope != name);
+
+        return self;
+    }
+
+    pub fn without_global_scopes(&mut self) -> &mut Self {
+        self.scopes.clear();
+
+        return self;
+    }
+
+    /// Loads a relation of the fetched models with one `IN` query, nested relations are separated by dots.
+    ///
+    /// ```ignore
+    /// let users = User::query(&db).with("orders.items").all().await?;
+    /// ```
+    pub fn with(&mut self, relation: &str) -> &mut Self {
+        Eager::insert(&mut self.eager, relation, None);
+
+        return self;
+    }
+
+    /// Same as `with`, constraining the query on the relation's model `R` like a local scope.
+    ///
+    /// ```ignore
+    /// User::query(&db).with_where("orders", |query: &mut Query<SQLite, Order>| {
+    ///     query.r#where("orders.paid", "=", false);
+    /// });
+    /// ```
+    pub fn with_where<R, F>(&mut self, relation: &str, constraint: F) -> &mut Self
+    where
+        R: Model,
+        F: Fn(&mut Query<'_, E, R>) + Send + Sync + 'static
+    {
+        let constraint: Scope<E, R> = Arc::new(constraint);
+
+        Eager::insert(&mut self.eager, relation, Some(Arc::new(constraint)));
+
+        return self;
+    }
+
+    /// Makes running the query return the error, for a value the builders couldn't bind.
+    fn fail(&mut self, error: anyhow::Error) -> &mut Self {
+        self.statement.arguments.fail(error);
+
+        return self;
+    }
+
+    /// Appends the conditions every query of the model gets, once, after the caller's where clauses.
+    fn apply_scopes(&mut self) {
+        if self.scoped {
+            return;
+        }
+
+        self.scoped = true;
+
+        for (_, scope) in std::mem::take(&mut self.scopes) {
+            self.scope(|query| scope(query));
+        }
+
+        let Some(deleted_at) = self.soft_deletes else {
+            return;
+        };
+
+        let column = format!("{}.{}", self.statement.query.table, deleted_at);
+
+        match self.trashed {
+            Trashed::Without => {
+                self.statement.query.group_where();
+                self.statement.query.and_where(&column, "IS NULL");
+            },
+            Trashed::Only => {
+                self.statement.query.group_where();
+                self.statement.query.and_where(&column, "IS NOT NULL");
+            },
+            Trashed::With => {},
+        }
+    }
+
+    /// The conditions of the global scopes and soft deletes on their own, for `Update` to add after the caller's `where` clauses.
+    fn scope_statement(&mut self) -> Statement<'q, E::T> {
+        let table = self.statement.query.table.clone();
+        let statement = std::mem::replace(&mut self.statement, Statement::new(&table));
+
+        self.apply_scopes();
+
+        return std::mem::replace(&mut self.statement, statement);
     }
 
     pub fn table(&mut self, name: &'q str) -> &mut Self {
@@ -140,6 +387,13 @@ where
     }
 
     pub fn r#where<T: 'q + Encode<'q, E::T> + Type<E::T>>(&mut self, column: &str, operator: &str, val: T) -> &mut Self {
+        if let Some((index, hash)) = blind_index(self.blind_indexes, column, &val) {
+            return match hash {
+                Ok(hash) => self.r#where(index, operator, <E::T as Driver>::Argument::from(hash)),
+                Err(error) => self.fail(error),
+            };
+        }
+
         if self.statement.query.where_queries.len() != 0 {
             return self.and_where(column, operator, val);
         }
@@ -149,15 +403,23 @@ where
             column: Some(column.to_string()),
             operator: Some(operator.to_string()),
             position: None,
-            group: None
+            group: None,
+            values: None
         });
 
-        self.statement.arguments.add(val).unwrap();
+        self.statement.arguments.push(val);
         
         return self;
     }
 
     pub fn and_where<T: 'q + Encode<'q, E::T> + Type<E::T>>(&mut self, column: &str, operator: &str, val: T) -> &mut Self {
+        if let Some((index, hash)) = blind_index(self.blind_indexes, column, &val) {
+            return match hash {
+                Ok(hash) => self.and_where(index, operator, <E::T as Driver>::Argument::from(hash)),
+                Err(error) => self.fail(error),
+            };
+        }
+
         if self.statement.query.where_queries.len() == 0 {
             return self.r#where(column, operator, val);
         }
@@ -166,15 +428,23 @@ where
             column: Some(column.to_string()),
             operator: Some(operator.to_string()),
             position: Some(query::QueryPosition::AND),
-            group: None
+            group: None,
+            values: None
         });
 
-        self.statement.arguments.add(val).unwrap();
+        self.statement.arguments.push(val);
         
         return self;
     }
 
     pub fn or_where<T: 'q + Encode<'q, E::T> + Type<E::T>>(&mut self, column: &str, operator: &str, val: T) -> &mut Self {
+        if let Some((index, hash)) = blind_index(self.blind_indexes, column, &val) {
+            return match hash {
+                Ok(hash) => self.or_where(index, operator, <E::T as Driver>::Argument::from(hash)),
+                Err(error) => self.fail(error),
+            };
+        }
+
         if self.statement.query.where_queries.len() == 0 {
             return self.r#where(column, operator, val);
         }
@@ -183,23 +453,54 @@ where
             column: Some(column.to_string()),
             operator: Some(operator.to_string()),
             position: Some(query::QueryPosition::OR),
-            group: None
+            group: None,
+            values: None
         });
 
-        self.statement.arguments.add(val).unwrap();
+        self.statement.arguments.push(val);
 
         return self;
     }
 
-    pub fn where_group(&mut self, callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
+    /// `column IN (...)` joined with `AND`, an empty list matches no rows.
+    pub fn where_in<V: Into<Value>>(&mut self, column: &str, values: Vec<V>) -> &mut Self {
+        let mut column = column;
+        let mut values = values.into_iter().map(Into::into).collect::<Vec<Value>>();
+
+        if let Some((_, index)) = self.blind_indexes.iter().find(|(encrypted, _)| *encrypted == column) {
+            column = index;
+            values = match values.iter().map(encryption::index).collect() {
+                Ok(values) => values,
+                Err(error) => return self.fail(error),
+            };
+        }
+
+        self.statement.query.where_queries.push(WhereQuery {
+            column: Some(column.to_string()),
+            operator: Some("IN".to_string()),
+            position: if self.statement.query.where_queries.len() == 0 { None } else { Some(QueryPosition::AND) },
+            group: None,
+            values: Some(values.len())
+        });
+
+        for value in values {
+            if let Err(error) = E::T::bind(&mut self.statement.arguments, value) {
+                self.statement.arguments.fail(error);
+            }
+        }
+
         return self;
     }
 
-    pub fn and_where_group(&mut self, callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
+    pub fn where_group(&mut self, _callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
         return self;
     }
 
-    pub fn or_where_group(&mut self, callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
+    pub fn and_where_group(&mut self, _callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
+        return self;
+    }
+
+    pub fn or_where_group(&mut self, _callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
         return self;
     }
 
@@ -230,8 +531,52 @@ where
         return self;
     }
 
+    /// Locks the selected rows with `FOR UPDATE` until the transaction ends, run it on a `Database::transaction`.
+    ///
+    /// SQLite has no row locks, the clause is left out there: its writers are serialized, and a transaction that read
+    /// rows another one wrote since fails with `SQLITE_BUSY` on its first write.
+    ///
+    /// ```ignore
+    /// let transaction = db.transaction().await?;
+    /// let job = Job::query(&transaction).r#where("status", "=", "pending").lock_for_update().skip_locked().first().await?;
+    /// ```
+    pub fn lock_for_update(&mut self) -> &mut Self {
+        return self.lock(LockMode::Update);
+    }
+
+    /// Locks the selected rows with `FOR SHARE`, so they can't change until the transaction ends. MySQL needs version 8.
+    pub fn lock_shared(&mut self) -> &mut Self {
+        return self.lock(LockMode::Share);
+    }
+
+    /// Leaves out the rows locked by another transaction instead of waiting for them, locks `FOR UPDATE` when not locked yet.
+    pub fn skip_locked(&mut self) -> &mut Self {
+        return self.lock_wait(LockWait::SkipLocked);
+    }
+
+    /// Fails when a row is locked by another transaction instead of waiting for it, locks `FOR UPDATE` when not locked yet.
+    pub fn no_wait(&mut self) -> &mut Self {
+        return self.lock_wait(LockWait::NoWait);
+    }
+
+    fn lock(&mut self, mode: LockMode) -> &mut Self {
+        let wait = self.statement.query.lock.map(|lock| lock.wait).unwrap_or_default();
+
+        self.statement.query.lock = Some(Lock { mode: mode, wait: wait });
+
+        return self;
+    }
+
+    fn lock_wait(&mut self, wait: LockWait) -> &mut Self {
+        let mode = self.statement.query.lock.map(|lock| lock.mode).unwrap_or(LockMode::Update);
+
+        self.statement.query.lock = Some(Lock { mode: mode, wait: wait });
+
+        return self;
+    }
+
     pub fn bind<T: 'q + Encode<'q, E::T> + Type<E::T>>(&'q mut self, value: T) -> &'q mut Self {
-        self.statement.arguments.add(value).unwrap();
+        self.statement.arguments.push(value);
 
         return self;
     }
@@ -240,7 +585,7 @@ where
     where
         O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        return Ok(self.db.query_all::<O, T>(sql, args).await.unwrap());
+        return self.db.query_all::<O, T>(sql, args).await;
     }
 
     // TODO: needs sub classes as insert_as to allow easy binding
@@ -248,7 +593,7 @@ where
     where
         O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        return Ok(self.db.query_all::<O, T>(sql, args).await.unwrap());
+        return self.db.query_all::<O, T>(sql, args).await;
     }
 
     // TODO: needs sub classes as insert_as to allow easy binding
@@ -256,47 +601,557 @@ where
     where
         O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        return Ok(self.db.query_one::<O, T>(sql, args).await.unwrap())
+        return self.db.query_one::<O, T>(sql, args).await;
+    }
+
+    /// Runs raw SQL with `:name` placeholders bound from a map or serializable struct.
+    pub async fn query_all_with<O, I, P: IntoValues<I>>(&'q mut self, sql: &str, params: P) -> Result<Vec<O>>
+    where
+        O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        let (sql, args) = query::named_parameters::<E::T>(sql, params.into_values()?)?;
+
+        return self.db.query_all::<O, <E::T as Driver>::Argument>(&sql, args.into_iter().map(Into::into).collect()).await;
+    }
+
+    /// Runs raw SQL with `:name` placeholders bound from a map or serializable struct.
+    pub async fn query_one_with<O, I, P: IntoValues<I>>(&'q mut self, sql: &str, params: P) -> Result<O>
+    where
+        O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        let (sql, args) = query::named_parameters::<E::T>(sql, params.into_values()?)?;
+
+        return self.db.query_one::<O, <E::T as Driver>::Argument>(&sql, args.into_iter().map(Into::into).collect()).await;
     }
 
+    /// Inserts and reads the row back, by its primary key when the model generates it or else by `rowid`.
+    ///
+    /// Models with a generated key get the key column added after `columns` when it is not one of them,
+    /// a text key bound for one of the `columns` is used as is. Binding any other type for the key fails
+    /// the insert, except on Postgres, as the row could not be read back by it. On SQLite, tables created
+    /// `WITHOUT ROWID` have no `rowid` to fall back to, so the insert fails unless the model generates the key.
     pub fn insert_as<O>(&'q mut self, columns: Vec<&str>) -> InsertAs<'q, E, O>
     where
         O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        self.statement.query.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        let position = self.generated_key
+            .and_then(|(key, _)| columns.iter().position(|column| *column == key).map(|position| (key, position + self.statement.arguments.len())));
+        let appended = self.insert_columns(columns);
 
-        return InsertAs::new(self.db, &mut self.statement);
+        return InsertAs::new(self.db, &mut self.statement).keyed(appended, position);
     }
 
+    /// Models with a generated key get the key column added after `columns` when it is not one of them,
+    /// models with timestamps get `created_at`/`updated_at` added the same way.
     pub fn insert(&'q mut self, columns: Vec<&str>) -> Insert<'q, E> {
-        self.statement.query.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        let appended = self.insert_columns(columns);
 
-        return Insert::new(&self.db, &mut self.statement);
+        return Insert::new(self.db, &mut self.statement).appended(appended);
     }
 
+    /// Sets the columns of an insert, adding the generated key and timestamps of the model after them when they don't have them.
+    ///
+    /// Returns the values of the added columns, bound by the builder after the values of `columns`.
+    fn insert_columns(&mut self, columns: Vec<&str>) -> Vec<Value> {
+        let mut columns = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let key = self.generated_key
+            .filter(|(key, _)| !columns.iter().any(|column| column == key))
+            .and_then(|(key, key_type)| key_type.generate().map(|value| (key.to_string(), value)));
+
+        if let Some((key, value)) = &key {
+            self.statement.query.key = Some((key.clone(), value.clone()));
+        }
+
+        let appended = key.into_iter()
+            .chain(self.timestamp_values(true))
+            .filter(|(column, _)| !columns.contains(column))
+            .collect::<Vec<_>>();
+
+        columns.extend(appended.iter().map(|(column, _)| column.clone()));
+
+        self.statement.query.columns = Some(columns);
+
+        return appended.into_iter().map(|(_, value)| value).collect();
+    }
+
+    /// Models with a version column get it incremented unless it is one of `columns`, use `Update::versioned` to only
+    /// update rows still at the version read. Models with timestamps get `updated_at` set unless it is one of them.
     pub fn update(&'q mut self, columns: Vec<&str>) -> Update<'q, E> {
-        self.statement.query.columns = Some(columns.iter().map(|c| c.to_string()).collect());
+        let mut columns = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        if let Some((_, version)) = self.version.filter(|(_, version)| !columns.iter().any(|column| column == version)) {
+            self.statement.query.raw_columns.push((version.to_string(), format!("{} + 1", version)));
+        }
+
+        let appended = self.timestamp_values(false)
+            .into_iter()
+            .filter(|(column, _)| !columns.contains(column))
+            .collect::<Vec<_>>();
+
+        columns.extend(appended.iter().map(|(column, _)| column.clone()));
+
+        self.statement.query.columns = Some(columns);
+
+        let scoped = self.scope_statement();
+
+        return Update::new(self.db, &mut self.statement)
+            .scoped(scoped)
+            .appended(appended.into_iter().map(|(_, value)| value).collect())
+            .indexed(self.blind_indexes)
+            .version(self.version);
+    }
+
+    /// The model's `created_at` and `updated_at` set to now for an insert, `updated_at` for an update, none after `touch(false)`.
+    fn timestamp_values(&self, insert: bool) -> Vec<(String, Value)> {
+        let Some((created_at, updated_at)) = self.timestamps.filter(|_| self.touch) else {
+            return Vec::new();
+        };
+
+        let now = Value::DateTime(chrono::Utc::now());
+        let columns = if insert { vec![created_at, updated_at] } else { vec![updated_at] };
+
+        return columns.into_iter().map(|column| (column.to_string(), now.clone())).collect();
+    }
+
+    /// Inserts the columns and values of a map of `Value`s or serializable struct, `None` fields are inserted as `NULL`.
+    ///
+    /// On a model query the columns the model does not allow to be mass assigned are dropped or rejected, see `Model::FILLABLE`.
+    pub fn insert_with<I, V: IntoValues<I>>(&'q mut self, values: V) -> Insert<'q, E> {
+        let error = self.fill(values).and_then(|values| self.bind_values(values, false, true)).err();
 
-        return Update::new(&self.db, &mut self.statement);
+        return Insert::new(self.db, &mut self.statement).failed(error);
     }
 
-    pub async fn delete(&'q mut self) -> Result<()>
+    /// Same as `insert_with` but leaves out `None` fields so the column defaults apply.
+    pub fn insert_with_skip_none<I, V: IntoValues<I>>(&'q mut self, values: V) -> Insert<'q, E> {
+        let error = self.fill(values).and_then(|values| self.bind_values(values, true, true)).err();
+
+        return Insert::new(self.db, &mut self.statement).failed(error);
+    }
+
+    /// Sets the columns and values of a map of `Value`s or serializable struct, `None` fields set the column to `NULL`.
+    ///
+    /// The values are bound when called, so call it before adding `where` clauses.
+    /// On a model query the columns the model does not allow to be mass assigned are dropped or rejected, see `Model::FILLABLE`.
+    /// The version column of a model is incremented, use `Update::versioned` to only update rows still at the version read.
+    pub fn update_with<I, V: IntoValues<I>>(&'q mut self, values: V) -> Update<'q, E> {
+        let error = self.fill(values).and_then(|values| self.bind_values(values, false, false)).err();
+        let scoped = self.scope_statement();
+
+        return Update::new(self.db, &mut self.statement).scoped(scoped).failed(error).indexed(self.blind_indexes).version(self.version);
+    }
+
+    /// Same as `update_with` but leaves `None` fields untouched.
+    pub fn update_with_skip_none<I, V: IntoValues<I>>(&'q mut self, values: V) -> Update<'q, E> {
+        let error = self.fill(values).and_then(|values| self.bind_values(values, true, false)).err();
+        let scoped = self.scope_statement();
+
+        return Update::new(self.db, &mut self.statement).scoped(scoped).failed(error).indexed(self.blind_indexes).version(self.version);
+    }
+
+    /// Inserts the values, or updates the `update` columns of the row with the same `unique_by` columns when there is one.
+    ///
+    /// MySQL matches the row on any unique index of the table instead of `unique_by`. On a model query the columns the
+    /// model does not allow to be mass assigned are dropped or rejected like `insert_with` does, and never updated.
+    /// Models with timestamps get `updated_at` updated too, the version column is left as it is.
+    pub fn upsert_with<I, V: IntoValues<I>>(&'q mut self, values: V, unique_by: Vec<&str>, update: Vec<&str>) -> Insert<'q, E> {
+        let mut error = self.fill(values).and_then(|values| self.bind_values(values, false, true)).err();
+
+        if unique_by.len() == 0 {
+            error.get_or_insert(anyhow::anyhow!("upsert_with needs the unique columns rows are matched on"));
+        }
+
+        let columns = self.statement.query.columns.clone().unwrap_or_default();
+        let mut update = update.into_iter()
+            .filter(|column| columns.iter().any(|c| c == column))
+            .map(|column| column.to_string())
+            .collect::<Vec<_>>();
+
+        if let (Some((_, updated_at)), true) = (self.timestamps, self.touch)
+            && !update.iter().any(|column| column == updated_at)
+        {
+            update.push(updated_at.to_string());
+        }
+
+        self.statement.query.upsert = Some((unique_by.iter().map(|column| column.to_string()).collect(), update));
+
+        return Insert::new(self.db, &mut self.statement).failed(error);
+    }
+
+    /// The values a client may set, with the model's guard applied.
+    pub(crate) fn fill<I, V: IntoValues<I>>(&self, values: V) -> Result<Vec<(String, Value)>> {
+        let values = values.into_values()?;
+
+        return match &self.guard {
+            Some(guard) => guard.filter(values),
+            None => Ok(values),
+        };
+    }
+
+    /// Binds the values through the casts of the model, filling in its timestamps and version. Returns the timestamps.
+    fn bind_values<I, V: IntoValues<I>>(&mut self, values: V, skip_none: bool, insert: bool) -> Result<Vec<(String, Value)>> {
+        let mut values = values.into_values()?
+            .into_iter()
+            .filter(|(_, value)| !(skip_none && value.is_null()))
+            .collect::<Vec<_>>();
+
+        if let Some(cast_values) = self.cast_values {
+            values = cast_values(values)?;
+        }
+
+        if let (Some((key, key_type)), true) = (self.generated_key, insert) {
+            let value = values.iter()
+                .position(|(column, _)| column == key)
+                .map(|index| values.remove(index).1)
+                .filter(|value| !value.is_null() && *value != Value::Text(String::new()))
+                .or_else(|| key_type.generate())
+                .unwrap();
+
+            self.statement.query.key = Some((key.to_string(), value.clone()));
+
+            values.push((key.to_string(), value));
+        }
+
+        // A primary key given with the values reads the inserted row back by it, instead of the last insert id.
+        if let (Some(primary_key), None, true) = (&self.statement.query.primary_key, &self.statement.query.key, insert)
+            && let Some((column, value)) = values.iter().find(|(column, value)| column == primary_key && !value.is_null())
+        {
+            self.statement.query.key = Some((column.clone(), value.clone()));
+        }
+
+        if let Some((_, version)) = self.version {
+            let value = values.iter()
+                .position(|(column, _)| column == version)
+                .map(|index| values.remove(index).1)
+                .filter(|value| !value.is_null())
+                .unwrap_or(Value::Int(1));
+
+            if insert {
+                values.push((version.to_string(), value));
+            } else {
+                self.statement.query.raw_columns.push((version.to_string(), format!("{} + 1", version)));
+            }
+        }
+
+        let timestamps = self.timestamp_values(insert);
+
+        values.retain(|(column, _)| !timestamps.iter().any(|(timestamp, _)| timestamp == column));
+        values.extend(timestamps.iter().cloned());
+
+        self.statement.query.columns = Some(values.iter().map(|(column, _)| column.clone()).collect());
+
+        for (_, value) in values {
+            E::T::bind(&mut self.statement.arguments, value)?;
+        }
+
+        return Ok(timestamps);
+    }
+
+    /// Deletes the rows, models with soft deletes get their `deleted_at` set instead.
+    pub async fn delete(&mut self) -> Result<()>
     {
-        return Ok(self.db.delete(&self.statement).await.unwrap())
+        self.apply_scopes();
+
+        if let Some(deleted_at) = self.soft_deletes {
+            self.statement.query.columns = Some(Vec::new());
+            self.statement.query.raw_columns = vec![(deleted_at.to_string(), "CURRENT_TIMESTAMP".to_string())];
+
+            self.db.update(&self.statement).await?;
+
+            return Ok(());
+        }
+
+        return self.db.delete(&self.statement).await;
+    }
+
+    /// Deletes the rows even when the model has soft deletes.
+    pub async fn force_delete(&mut self) -> Result<()> {
+        if self.trashed == Trashed::Without {
+            self.trashed = Trashed::With;
+        }
+
+        self.apply_scopes();
+
+        return self.db.delete(&self.statement).await;
+    }
+
+    /// Clears `deleted_at` of the soft deleted rows.
+    pub async fn restore(&mut self) -> Result<()> {
+        let Some(deleted_at) = self.soft_deletes else {
+            return Err(anyhow::anyhow!("`{}` does not have soft deletes", self.statement.query.table));
+        };
+
+        if self.trashed == Trashed::Without {
+            self.trashed = Trashed::Only;
+        }
+
+        self.apply_scopes();
+
+        self.statement.query.columns = Some(Vec::new());
+        self.statement.query.raw_columns = vec![(deleted_at.to_string(), "NULL".to_string())];
+
+        self.db.update(&self.statement).await?;
+
+        return Ok(());
+    }
+
+    pub async fn count(&mut self) -> Result<u64> {
+        self.apply_scopes();
+
+        return self.db.count(&self.statement).await;
+    }
+
+    /// Keyset pagination over the `order_by` columns, which together must be unique.
+    ///
+    /// Pass `None` for the first page and a `next_cursor`/`prev_cursor` token from a previous page after that.
+    pub async fn cursor_paginate<O>(&'q mut self, limit: u64, cursor: Option<&str>) -> Result<CursorPagination<O>>
+    where
+        O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        let orders = self.statement.query.order_by.clone();
+
+        self.apply_scopes();
+
+        if orders.len() == 0 {
+            return Err(anyhow::anyhow!("cursor pagination needs an order_by on unique columns"));
+        }
+
+        let cursor = cursor.map(Cursor::decode).transpose()?;
+        let forward = cursor.as_ref().map(|cursor| cursor.forward).unwrap_or(true);
+
+        if self.statement.query.select.len() == 0 {
+            self.statement.query.select.push("*".to_string());
+        }
+
+        for (i, order) in orders.iter().enumerate() {
+            self.statement.query.select.push(format!("{} AS {}", order.column, query::key_alias(i)));
+        }
+
+        if !forward {
+            self.statement.query.order_by = orders.iter().map(|order| OrderQuery {
+                column: order.column.clone(),
+                order: match order.order { Order::ASC => Order::DESC, Order::DESC => Order::ASC }
+            }).collect();
+        }
+
+        if let Some(cursor) = &cursor {
+            let values = cursor.values()?;
+
+            if values.len() != orders.len() {
+                return Err(anyhow::anyhow!("cursor does not match the query order"));
+            }
+
+            self.statement.query.group_where();
+            self.cursor_where(&orders, values, forward)?;
+        }
+
+        self.statement.query.limit = Some(limit + 1);
+
+        let mut items = self.db.all::<Keyed<E::T, O>>(&self.statement).await?;
+        let has_more = items.len() as u64 > limit;
+
+        items.truncate(limit as usize);
+
+        if !forward {
+            items.reverse();
+        }
+
+        let first = items.first().map(|item| Cursor::encode(false, &item.keys));
+        let last = items.last().map(|item| Cursor::encode(true, &item.keys));
+
+        return Ok(CursorPagination {
+            per_page: limit,
+            next_cursor: if (forward && has_more) || (!forward && cursor.is_some()) { last } else { None },
+            prev_cursor: if (!forward && has_more) || (forward && cursor.is_some()) { first } else { None },
+            items: items.into_iter().map(|item| item.row).collect(),
+        });
     }
 
+    /// Appends `(a > ?) OR (a = ? AND b > ?) ...` for the orders, flipping the comparison for descending columns.
+    fn cursor_where(&mut self, orders: &[OrderQuery], values: Vec<Value>, forward: bool) -> Result<()> {
+        let mut branches: Vec<WhereQuery> = Vec::new();
+
+        for (i, order) in orders.iter().enumerate() {
+            let mut conditions: Vec<WhereQuery> = Vec::new();
+
+            for (j, previous) in orders[..i].iter().enumerate() {
+                conditions.push(WhereQuery {
+                    column: Some(previous.column.clone()),
+                    operator: Some("=".to_string()),
+                    position: if j == 0 { None } else { Some(QueryPosition::AND) },
+                    group: None,
+                    values: None
+                });
+
+                E::T::bind(&mut self.statement.arguments, values[j].clone())?;
+            }
+
+            let ascending = matches!(order.order, Order::ASC) == forward;
+
+            conditions.push(WhereQuery {
+                column: Some(order.column.clone()),
+                operator: Some(if ascending { ">" } else { "<" }.to_string()),
+                position: if i == 0 { None } else { Some(QueryPosition::AND) },
+                group: None,
+                values: None
+            });
+
+            E::T::bind(&mut self.statement.arguments, values[i].clone())?;
+
+            branches.push(WhereQuery {
+                column: None,
+                operator: None,
+                position: if i == 0 { None } else { Some(QueryPosition::OR) },
+                group: Some(conditions),
+                values: None
+            });
+        }
+
+        self.statement.query.where_queries.push(WhereQuery {
+            column: None,
+            operator: None,
+            position: if self.statement.query.where_queries.len() == 0 { None } else { Some(QueryPosition::AND) },
+            group: Some(branches),
+            values: None
+        });
+
+        return Ok(());
+    }
+
+    /// Streams the rows one at a time instead of loading them all into memory.
+    ///
+    /// In a transaction the rows are read before the first one is yielded, so other queries of the transaction can run meanwhile.
+    pub fn stream<O>(&'q mut self) -> BoxStream<'q, Result<O>>
+    where
+        O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized + 'q
+    {
+        self.apply_scopes();
+
+        return self.db.stream::<O>(&self.statement);
+    }
+
+    /// Pages through the rows `size` at a time using limit and offset.
+    ///
+    /// Add an `order_by` on a unique column for stable pages, or use `chunk_by_id` when the
+    /// callback changes the rows it is paging through.
+    pub async fn chunk<O, F, Fut>(&'q mut self, size: u64, mut callback: F) -> Result<()>
+    where
+        O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized,
+        F: FnMut(Vec<O>) -> Fut,
+        Fut: Future<Output = Result<()>>
+    {
+        let mut page = 1;
+
+        self.apply_scopes();
+
+        loop {
+            self.statement.query.limit = Some(size);
+            self.statement.query.page = Some(page);
+
+            let items = self.db.all::<O>(&self.statement).await?;
+            let count = items.len() as u64;
+
+            if count == 0 {
+                break;
+            }
+
+            callback(items).await?;
+
+            if count < size {
+                break;
+            }
+
+            page += 1;
+        }
+
+        return Ok(());
+    }
+
+    /// Pages through the rows `size` at a time ordered by the `id` column.
+    pub async fn chunk_by_id<O, F, Fut>(&'q mut self, size: u64, callback: F) -> Result<()>
+    where
+        O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized,
+        F: FnMut(Vec<O>) -> Fut,
+        Fut: Future<Output = Result<()>>
+    {
+        return self.chunk_by_column("id", size, callback).await;
+    }
+
+    /// Pages through the rows `size` at a time using `column > last seen value`, the column must be unique.
+    pub async fn chunk_by_column<O, F, Fut>(&'q mut self, column: &str, size: u64, mut callback: F) -> Result<()>
+    where
+        O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized,
+        F: FnMut(Vec<O>) -> Fut,
+        Fut: Future<Output = Result<()>>
+    {
+        let mut last: Option<Value> = None;
+
+        self.apply_scopes();
+
+        if self.statement.query.select.len() == 0 {
+            self.statement.query.select.push("*".to_string());
+        }
+
+        self.statement.query.select.push(format!("{} AS {}", column, query::key_alias(0)));
+        self.statement.query.order_by = vec![OrderQuery { column: column.to_string(), order: Order::ASC }];
+        self.statement.query.limit = Some(size);
+        self.statement.query.group_where();
+
+        loop {
+            let mut statement = self.statement.duplicate();
+
+            if let Some(last) = last.take() {
+                statement.query.and_where(column, ">");
+
+                E::T::bind(&mut statement.arguments, last)?;
+            }
+
+            let items = self.db.all::<Keyed<E::T, O>>(&statement).await?;
+            let count = items.len() as u64;
+
+            let Some(item) = items.last() else {
+                break;
+            };
+
+            last = Some(item.keys[0].clone());
+
+            callback(items.into_iter().map(|item| item.row).collect()).await?;
+
+            if count < size {
+                break;
+            }
+        }
+
+        return Ok(());
+    }
+
+    pub fn to_sql(&'q mut self) -> Result<String> {
+        self.apply_scopes();
+
+        return self.db.to_sql(&self.statement);
+    }
+}
+
+impl <'q, E>Query<'q, E>
+where
+    E: Executor
+{
     pub async fn first<O>(&'q mut self) -> Result<O>
     where
         O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        return Ok(self.db.first::<O>(&self.statement).await.unwrap())
+        self.apply_scopes();
+
+        return self.db.first::<O>(&self.statement).await;
     }
 
     pub async fn all<O>(&'q mut self) -> Result<Vec<O>>
     where
         O: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        return Ok(self.db.all::<O>(&self.statement).await.unwrap())
+        self.apply_scopes();
+
+        return self.db.all::<O>(&self.statement).await;
     }
 
     pub async fn paginate<O>(&'q mut self, limit: u64, page: u64) -> Result<Pagination<O>>
@@ -306,17 +1161,18 @@ where
         self.statement.query.limit = Some(limit);
         self.statement.query.page = Some(page); // TODO: calc offset using offset
 
-        return Ok(self.db.paginate::<O>(&self.statement).await.unwrap());
-    }
+        self.apply_scopes();
 
-    pub fn to_sql(&'q mut self) -> Result<String> {
-        return Ok(self.db.to_sql(&self.statement).unwrap())
+        return self.db.paginate::<O>(&self.statement).await;
     }
 }
 
 pub struct InsertAs<'q, E: Executor, O> {
     db: &'q E,
     statement: &'q mut Statement<'q, E::T>,
+    appended: Vec<Value>,
+    /// The key column and the position of its argument, when the key is bound by the caller.
+    key_position: Option<(&'static str, usize)>,
     _marker: PhantomData<E>,
     _type: PhantomData<O>
 }
@@ -330,25 +1186,51 @@ where
         return Self {
             db: db,
             statement: statement,
+            appended: Vec::new(),
+            key_position: None,
             _marker: PhantomData,
             _type: PhantomData
         }
     }
 
+    /// Values of columns added after the caller's, like a generated key or timestamps, and the position of the key bound by the caller.
+    pub(crate) fn keyed(mut self, appended: Vec<Value>, key_position: Option<(&'static str, usize)>) -> Self {
+        self.appended = appended;
+        self.key_position = key_position;
+
+        return self;
+    }
+
     pub fn bind<T: 'q + Encode<'q, E::T> + Type<E::T>>(&'q mut self, value: T) -> &'q mut Self {
-        self.statement.arguments.add(value).unwrap();
+        if let Some((key, position)) = self.key_position
+            && position == self.statement.arguments.len()
+            && !E::T::RETURNING
+        {
+            match E::T::text(&value) {
+                Some(text) => self.statement.query.key = Some((key.to_string(), Value::Text(text))),
+                None => self.statement.arguments.fail(anyhow::anyhow!("`insert_as` reads the row back by `{}`, bind it as text", key)),
+            }
+        }
+
+        self.statement.arguments.push(value);
 
         return self;
     }
 
     pub async fn execute(&'q mut self) -> Result<O> {
-        return Ok(self.db.insert_as::<O>(self.statement).await.unwrap());
+        for value in std::mem::take(&mut self.appended) {
+            E::T::bind(&mut self.statement.arguments, value)?;
+        }
+
+        return self.db.insert_as::<O>(self.statement).await;
     }
 }
 
 pub struct Insert<'q, E: Executor> {
     db: &'q E,
     statement: &'q mut Statement<'q, E::T>,
+    error: Option<anyhow::Error>,
+    appended: Vec<Value>,
     _marker: PhantomData<E>
 }
 
@@ -360,17 +1242,41 @@ where
         return Self {
             db: db,
             statement: statement,
+            error: None,
+            appended: Vec::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Makes `execute` return the error of building the values.
+    pub(crate) fn failed(mut self, error: Option<anyhow::Error>) -> Self {
+        self.error = error;
+
+        return self;
+    }
+
+    /// Values of columns added after the caller's, like a generated key or timestamps, bound after theirs.
+    pub(crate) fn appended(mut self, appended: Vec<Value>) -> Self {
+        self.appended = appended;
+
+        return self;
+    }
+
     pub fn bind<T: 'q + Encode<'q, E::T> + Type<E::T>>(&'q mut self, value: T) -> &'q mut Self {
-        self.statement.arguments.add(value).unwrap();
+        self.statement.arguments.push(value);
 
         return self;
     }
 
     pub async fn execute(&'q mut self) -> Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        for value in std::mem::take(&mut self.appended) {
+            E::T::bind(&mut self.statement.arguments, value)?;
+        }
+
         return self.db.insert(self.statement).await;
     }
 }
@@ -378,6 +1284,14 @@ where
 pub struct Update<'q, E: Executor> {
     db: &'q E,
     statement: &'q mut Statement<'q, E::T>,
+    error: Option<anyhow::Error>,
+    appended: Vec<Value>,
+    blind_indexes: &'static [(&'static str, &'static str)],
+    /// The table and version column of the model, `versioned` sets whether the version is checked.
+    version: Option<(&'static str, &'static str)>,
+    versioned: bool,
+    /// The conditions of the global scopes and soft deletes, added around the caller's when executed.
+    scoped: Option<Statement<'q, E::T>>,
     _marker: PhantomData<E>
 }
 
@@ -389,18 +1303,81 @@ where
         return Self {
             db: db,
             statement: statement,
+            error: None,
+            appended: Vec::new(),
+            blind_indexes: &[],
+            version: None,
+            versioned: false,
+            scoped: None,
             _marker: PhantomData,
         }
     }
 
+    /// Makes `execute` return the error of building the values.
+    pub(crate) fn failed(mut self, error: Option<anyhow::Error>) -> Self {
+        self.error = error;
+
+        return self;
+    }
+
+    /// Values of columns added after the caller's, like `updated_at`, bound before the first `where` value.
+    pub(crate) fn appended(mut self, appended: Vec<Value>) -> Self {
+        self.appended = appended;
+
+        return self;
+    }
+
+    /// Restricts the update to the rows the model's global scopes and soft deletes let a query see.
+    pub(crate) fn scoped(mut self, scoped: Statement<'q, E::T>) -> Self {
+        self.scoped = Some(scoped);
+
+        return self;
+    }
+
+    /// Lets `versioned` compare the version column of the model.
+    pub(crate) fn version(mut self, version: Option<(&'static str, &'static str)>) -> Self {
+        self.version = version;
+
+        return self;
+    }
+
+    /// Makes `execute` return the error, for a value the builders couldn't bind.
+    fn fail(&mut self, error: anyhow::Error) -> &mut Self {
+        self.statement.arguments.fail(error);
+
+        return self;
+    }
+
+    fn bind_appended(&mut self) {
+        for value in std::mem::take(&mut self.appended) {
+            if let Err(error) = E::T::bind(&mut self.statement.arguments, value) {
+                self.error.get_or_insert(error);
+            }
+        }
+    }
+
+    /// Makes `where` on encrypted columns compare their blind index columns.
+    pub(crate) fn indexed(mut self, blind_indexes: &'static [(&'static str, &'static str)]) -> Self {
+        self.blind_indexes = blind_indexes;
+
+        return self;
+    }
+
     pub fn bind<T: 'q + Encode<'q, E::T> + Type<E::T>>(&'q mut self, value: T) -> &'q mut Self {
-        self.statement.arguments.add(value).unwrap();
+        self.statement.arguments.push(value);
 
         return self;
     }
 
 
     pub fn r#where<T: 'q + Encode<'q, E::T> + Type<E::T>>(&mut self, column: &str, operator: &str, val: T) -> &mut Self {
+        if let Some((index, hash)) = blind_index(self.blind_indexes, column, &val) {
+            return match hash {
+                Ok(hash) => self.r#where(index, operator, <E::T as Driver>::Argument::from(hash)),
+                Err(error) => self.fail(error),
+            };
+        }
+
         if self.statement.query.where_queries.len() != 0 {
             return self.and_where(column, operator, val);
         }
@@ -410,15 +1387,24 @@ where
             column: Some(column.to_string()),
             operator: Some(operator.to_string()),
             position: None,
-            group: None
+            group: None,
+            values: None
         });
 
-        self.statement.arguments.add(val).unwrap();
+        self.bind_appended();
+        self.statement.arguments.push(val);
         
         return self;
     }
 
     pub fn and_where<T: 'q + Encode<'q, E::T> + Type<E::T>>(&mut self, column: &str, operator: &str, val: T) -> &mut Self {
+        if let Some((index, hash)) = blind_index(self.blind_indexes, column, &val) {
+            return match hash {
+                Ok(hash) => self.and_where(index, operator, <E::T as Driver>::Argument::from(hash)),
+                Err(error) => self.fail(error),
+            };
+        }
+
         if self.statement.query.where_queries.len() == 0 {
             return self.r#where(column, operator, val);
         }
@@ -427,15 +1413,24 @@ where
             column: Some(column.to_string()),
             operator: Some(operator.to_string()),
             position: Some(query::QueryPosition::AND),
-            group: None
+            group: None,
+            values: None
         });
 
-        self.statement.arguments.add(val).unwrap();
+        self.bind_appended();
+        self.statement.arguments.push(val);
         
         return self;
     }
 
     pub fn or_where<T: 'q + Encode<'q, E::T> + Type<E::T>>(&mut self, column: &str, operator: &str, val: T) -> &mut Self {
+        if let Some((index, hash)) = blind_index(self.blind_indexes, column, &val) {
+            return match hash {
+                Ok(hash) => self.or_where(index, operator, <E::T as Driver>::Argument::from(hash)),
+                Err(error) => self.fail(error),
+            };
+        }
+
         if self.statement.query.where_queries.len() == 0 {
             return self.r#where(column, operator, val);
         }
@@ -444,27 +1439,77 @@ where
             column: Some(column.to_string()),
             operator: Some(operator.to_string()),
             position: Some(query::QueryPosition::OR),
-            group: None
+            group: None,
+            values: None
         });
 
-        self.statement.arguments.add(val).unwrap();
+        self.bind_appended();
+        self.statement.arguments.push(val);
 
         return self;
     }
 
-    pub fn where_group(&mut self, callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
+    pub fn where_group(&mut self, _callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
         return self;
     }
 
-    pub fn and_where_group(&mut self, callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
+    pub fn and_where_group(&mut self, _callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
         return self;
     }
 
-    pub fn or_where_group(&mut self, callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
+    pub fn or_where_group(&mut self, _callback: fn(group: WhereQueryGroup<'q, E::T>) -> WhereQueryGroup<'q, E::T>) -> &mut Self {        
         return self;
     }
 
-    pub async fn execute(&'q mut self) -> Result<()> {
-        return self.db.update(self.statement).await;
+    /// Only updates the rows still at `version`, like `Model::save` does, `execute` fails with `StaleModel` when none is.
+    ///
+    /// ```ignore
+    /// Post::query(&db).update(vec!["title"]).bind("new").r#where("id", "=", post.id).versioned(post.version).execute().await?;
+    /// ```
+    pub fn versioned(&mut self, version: i64) -> &mut Self {
+        let Some((_, column)) = self.version else {
+            return self.fail(anyhow::anyhow!("`versioned` needs a model with a version column"));
+        };
+
+        self.versioned = true;
+
+        return self.and_where(column, "=", <E::T as Driver>::Argument::from(Value::Int(version)));
     }
-}
\ No newline at end of file
+
+    /// Returns the number of rows the update affected.
+    pub async fn execute(&'q mut self) -> Result<u64> {
+        self.bind_appended();
+
+        if let Some(scoped) = self.scoped.take() && scoped.query.where_queries.len() != 0 {
+            self.statement.query.group_where();
+            self.statement.query.where_queries.push(WhereQuery {
+                column: None,
+                operator: None,
+                position: if self.statement.query.where_queries.len() == 0 { None } else { Some(QueryPosition::AND) },
+                group: Some(scoped.query.where_queries),
+                values: None
+            });
+            self.statement.arguments.extend(scoped.arguments);
+        }
+
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        let affected = self.db.update(self.statement).await?;
+
+        if let (Some((table, _)), true, 0) = (self.version, self.versioned, affected) {
+            return Err(StaleModel { table: table, key: Value::Null }.into());
+        }
+
+        return Ok(affected);
+    }
+}
+
+/// The blind index column of an encrypted column and the hash of a text value to compare it with.
+fn blind_index<'q, DB: Driver, T: Encode<'q, DB> + Type<DB>>(blind_indexes: &[(&str, &'static str)], column: &str, val: &T) -> Option<(&'static str, Result<Value>)> {
+    let (_, index) = blind_indexes.iter().find(|(encrypted, _)| *encrypted == column)?;
+    let text = DB::text(val)?;
+
+    return Some((index, encryption::blind_index(&text).map(Value::Text)));
+}
@@ -1,14 +1,11 @@
 #![feature(inherent_associated_types)]
-#![feature(associated_type_defaults)]
-#[allow(incomplete_features)]
-
+#![allow(incomplete_features, clippy::needless_return)]
 
 use std::env;
 
 use anyhow::Result;
-use flyer_orm::{DB, Database, Executor, Query, mysql::MySQL, sqlite::SQLite};
-use serde::{Serialize, de};
-use uuid::Uuid;
+use flyer_orm::{Database, sqlite::SQLite};
+use serde::Serialize;
 // use sqlx::Database;
 
 #[derive(Debug, sqlx::FromRow, Serialize)]
@@ -27,7 +24,7 @@ pub struct Connection;
 impl Connection {
     pub type T = SQLite;
 
-    pub fn url<'q>() -> String {
+    pub fn url() -> String {
         return match env::var("ENVIRONMENT").unwrap_or("testing".to_string()).as_str() {
             "production"  => env::var("DATABASE_URL").unwrap(), // TODO: fix temp variable...
             "development" => "./database.sqlite".to_string(),
@@ -0,0 +1,916 @@
+use std::{any::{Any, TypeId}, collections::HashMap, fmt, sync::{Arc, LazyLock, RwLock}};
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::{Serialize, Serializer, ser::Error};
+use sqlx::{FromRow, mysql::MySqlRow, postgres::PgRow, sqlite::SqliteRow};
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::{Database, Driver, Executor, IntoValues, Query, Value, observer::{self, Attributes, Observer}, query::{Arguments, Pagination}, relation::{self, BelongsToMany, Eager, Relation}, serialize::{Serialized, Visibility}};
+
+/// A global scope, applied to every query of the model `M` on executor `E`.
+pub type Scope<E, M> = Arc<dyn Fn(&mut Query<'_, E, M>) + Send + Sync>;
+
+/// A model the rows of every driver decode into with `FromRow`, so its relations can be loaded whatever the executor.
+///
+/// Implemented for every `#[derive(sqlx::FromRow)]` or casting `#[derive(Model)]` struct whose fields all drivers decode.
+pub trait Decodable: for<'r> FromRow<'r, SqliteRow> + for<'r> FromRow<'r, PgRow> + for<'r> FromRow<'r, MySqlRow> {}
+
+impl <T>Decodable for T
+where
+    T: for<'r> FromRow<'r, SqliteRow> + for<'r> FromRow<'r, PgRow> + for<'r> FromRow<'r, MySqlRow>
+{}
+
+/// Global scopes by model and executor, the values are `Scope<E, M>`.
+static GLOBAL_SCOPES: LazyLock<RwLock<HashMap<(TypeId, TypeId), Vec<(&'static str, Box<dyn Any + Send + Sync>)>>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// A struct stored as a row of `TABLE`, usually implemented with `#[derive(Model)]`.
+#[allow(async_fn_in_trait)]
+pub trait Model: Sized + Send + Unpin + 'static {
+    const TABLE: &'static str;
+    const PRIMARY_KEY: &'static str;
+    const COLUMNS: &'static [&'static str];
+
+    /// Whether `created_at` and `updated_at` are filled in on insert and update.
+    const TIMESTAMPS: bool = false;
+    const CREATED_AT: &'static str = "created_at";
+    const UPDATED_AT: &'static str = "updated_at";
+
+    /// Whether `delete` sets `deleted_at` instead of removing the row, soft deleted rows are left out of queries.
+    const SOFT_DELETES: bool = false;
+    const DELETED_AT: &'static str = "deleted_at";
+
+    /// Fields left out when the model is serialized, `#[orm(hidden("password"))]` with the derive.
+    const HIDDEN: &'static [&'static str] = &[];
+    /// When not empty, the only fields serialized, `#[orm(visible("uuid", "email"))]` with the derive.
+    const VISIBLE: &'static [&'static str] = &[];
+
+    /// When not empty, the only columns `create`, `insert_with`, `update_with` and `upsert_with` take from their values, `#[orm(fillable("name", "email"))]` with the derive.
+    const FILLABLE: &'static [&'static str] = &[];
+    /// Columns those methods never take from their values, like `is_admin`, `#[orm(guarded("is_admin"))]` with the derive.
+    const GUARDED: &'static [&'static str] = &[];
+    /// Whether a column that can't be mass assigned fails with `MassAssignment` instead of being dropped, `#[orm(reject_guarded)]` with the derive.
+    const REJECT_GUARDED: bool = false;
+
+    /// How the primary key of new rows is made, `#[orm(key_type = "uuid_v7")]` with the derive.
+    const KEY_TYPE: KeyType = KeyType::Increment;
+
+    /// The integer column `save` checks and increments to detect concurrent updates, `#[orm(version)]` with the derive.
+    const VERSION: Option<&'static str> = None;
+
+    /// Encrypted columns and the blind index columns `where` compares instead, `#[orm(encrypted, blind_index = "...")]` with the derive.
+    const BLIND_INDEXES: &'static [(&'static str, &'static str)] = &[];
+
+    /// Stored in the `{name}_type` column of polymorphic relations, `#[orm(morph_name = "...")]` with the derive.
+    const MORPH_NAME: &'static str = Self::TABLE;
+
+    /// The column names and values of the fields, failing when a cast can't encode a field.
+    ///
+    /// Encrypted fields are plain text here, `cast_values` encrypts them and fills their blind index when they are written.
+    fn values(&self) -> Result<Vec<(&'static str, Value)>>;
+
+    /// Builds the model from the values of its columns, used to load relations.
+    fn from_values(values: Vec<(String, Value)>) -> Result<Self>;
+
+    /// Sets the field of a column, used to apply the changes of `updating` hooks and increment the `VERSION` column.
+    ///
+    /// Generated by `#[derive(Model)]`, models implemented by hand fail when a hook changes a value.
+    fn set_value(&mut self, column: &str, value: Value) -> Result<()> {
+        let _ = value;
+
+        return Err(anyhow::anyhow!("`{}` can't set `{}`, implement `Model::set_value` to change it from a hook", Self::TABLE, column));
+    }
+
+    /// Runs column values about to be written through the casts of their fields and adds the blind index columns,
+    /// generated by `#[derive(Model)]` for models with casts.
+    #[doc(hidden)]
+    fn cast_values(values: Vec<(String, Value)>) -> Result<Vec<(String, Value)>> {
+        return Ok(values);
+    }
+
+    /// Loads a relation declared with `#[orm(has_many = "...")]` and friends onto the models, generated by `#[derive(Model)]`.
+    #[doc(hidden)]
+    fn eager_load<'a, E: Executor>(db: &'a E, models: &'a mut [Self], eager: &'a Eager) -> BoxFuture<'a, Result<()>> {
+        let _ = (db, models);
+
+        return Box::pin(async move { relation::unknown::<Self>(eager) });
+    }
+
+    /// The relation declared on the field `name`, generated by `#[derive(Model)]`.
+    fn relation(name: &str) -> Option<Relation> {
+        let _ = name;
+
+        return None;
+    }
+
+    /// Serializes the fields `HIDDEN`, `VISIBLE` and the per-call `visibility` allow.
+    ///
+    /// `#[derive(Model)]` writes the fields itself when the model has field lists or `serialize_as` renames,
+    /// other models are serialized with their own `Serialize` impl and have its keys filtered.
+    #[doc(hidden)]
+    fn serialize_visible<S: Serializer>(&self, serializer: S, visibility: &Visibility) -> std::result::Result<S::Ok, S::Error>
+    where
+        Self: Serialize
+    {
+        let serde_json::Value::Object(mut map) = serde_json::to_value(self).map_err(S::Error::custom)? else {
+            return self.serialize(serializer);
+        };
+
+        map.retain(|field, _| visibility.shows(field, Self::HIDDEN, Self::VISIBLE));
+
+        return map.serialize(serializer);
+    }
+
+    /// Serializes the model with hidden fields shown, e.g. `user.make_visible(&["email"])` for an admin response.
+    fn make_visible(self, fields: &[&str]) -> Serialized<Self> {
+        return Serialized::new(self).make_visible(fields);
+    }
+
+    fn make_hidden(self, fields: &[&str]) -> Serialized<Self> {
+        return Serialized::new(self).make_hidden(fields);
+    }
+
+    /// The value of a column, `Value::Null` when the model has no such field.
+    fn value(&self, column: &str) -> Result<Value> {
+        return Ok(self.values()?
+            .into_iter()
+            .find(|(name, _)| *name == column)
+            .map(|(_, value)| value)
+            .unwrap_or(Value::Null));
+    }
+
+    fn key(&self) -> Result<Value> {
+        return self.value(Self::PRIMARY_KEY);
+    }
+
+    #[doc(hidden)]
+    fn snapshot(&self) -> Option<&Original> {
+        return None;
+    }
+
+    #[doc(hidden)]
+    fn snapshot_mut(&mut self) -> Option<&mut Original> {
+        return None;
+    }
+
+    /// Takes the current values as the original ones, called after the model is loaded or saved.
+    fn sync_original(&mut self) -> Result<()> {
+        if self.snapshot().is_none() {
+            return Ok(());
+        }
+
+        let values = self.values()?;
+
+        *self.snapshot_mut().unwrap() = Original(Some(values));
+
+        return Ok(());
+    }
+
+    /// The values the model was loaded with, `None` when it has not been loaded or has no `Original` field.
+    fn original(&self) -> Option<&[(&'static str, Value)]> {
+        return self.snapshot().and_then(|original| original.0.as_deref());
+    }
+
+    /// The columns changed since the model was loaded, all of them when there is nothing to compare against
+    /// or a cast can't encode a field, so `save` writes them and fails with the cast's error.
+    fn dirty_fields(&self) -> Vec<&'static str> {
+        let (Some(original), Ok(values)) = (self.original(), self.values()) else {
+            return Self::COLUMNS.to_vec();
+        };
+
+        return values
+            .into_iter()
+            .filter(|(column, value)| !original.iter().any(|(c, v)| c == column && v == value))
+            .map(|(column, _)| column)
+            .collect();
+    }
+
+    fn is_dirty(&self) -> bool {
+        return self.dirty_fields().len() != 0;
+    }
+
+    /// A model with an `Original` field exists once it has been loaded or saved, so a key set by the client
+    /// still inserts. Without one it exists when its primary key is set, `NULL`, `0` and `""` count as unset.
+    fn exists(&self) -> bool {
+        if self.snapshot().is_some() {
+            return self.original().is_some();
+        }
+
+        return self.key().is_ok_and(|key| is_set(&key));
+    }
+
+    fn query<'q, E: Executor>(db: &'q Database<E>) -> Query<'q, E, Self> {
+        return query(&db.executor);
+    }
+
+    /// Adds a scope applied to every query of the model, replacing the scope with the same name.
+    ///
+    /// ```ignore
+    /// User::add_global_scope::<SQLite, _>("tenant", move |query| {
+    ///     query.r#where("tenant_id", "=", tenant_id);
+    /// });
+    /// ```
+    fn add_global_scope<E, F>(name: &'static str, scope: F)
+    where
+        E: Executor,
+        F: Fn(&mut Query<'_, E, Self>) + Send + Sync + 'static
+    {
+        let scope: Scope<E, Self> = Arc::new(scope);
+        let mut scopes = GLOBAL_SCOPES.write().unwrap();
+        let scopes = scopes.entry((TypeId::of::<Self>(), TypeId::of::<E>())).or_default();
+
+        scopes.retain(|(scope, _)| *scope != name);
+        scopes.push((name, Box::new(scope)));
+    }
+
+    fn remove_global_scope<E: Executor>(name: &str) {
+        if let Some(scopes) = GLOBAL_SCOPES.write().unwrap().get_mut(&(TypeId::of::<Self>(), TypeId::of::<E>())) {
+            scopes.retain(|(scope, _)| *scope != name);
+        }
+    }
+
+    /// Registers an observer whose hooks run around the model's creates, updates, deletes and restores.
+    fn observe<O: Observer<Self>>(observer: O) {
+        observer::observe::<Self, O>(observer);
+    }
+
+    async fn find<E, K>(db: &Database<E>, id: K) -> Result<Option<Self>>
+    where
+        E: Executor,
+        K: Into<Value>,
+        Self: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let mut query = Self::query(db);
+
+        return query.find(id).await;
+    }
+
+    async fn find_or_fail<E, K>(db: &Database<E>, id: K) -> Result<Self>
+    where
+        E: Executor,
+        K: Into<Value>,
+        Self: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let mut query = Self::query(db);
+
+        return query.find_or_fail(id).await;
+    }
+
+    async fn all<E>(db: &Database<E>) -> Result<Vec<Self>>
+    where
+        E: Executor,
+        Self: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let mut query = Self::query(db);
+
+        return query.all().await;
+    }
+
+    /// Inserts a row from a map of `Value`s or serializable struct and returns it, keeping only the columns allowed by `FILLABLE` and `GUARDED`.
+    async fn create<E, I, V>(db: &Database<E>, values: V) -> Result<Self>
+    where
+        E: Executor,
+        V: IntoValues<I>,
+        Self: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let transaction = observer::begin::<E, Self>(db).await?;
+        let mut query = Self::query(transaction.as_deref().unwrap_or(db));
+        let created = query.create(values).await;
+
+        drop(query);
+
+        return observer::finish(transaction, created).await;
+    }
+
+    /// Updates the row when the model exists, otherwise inserts it and reloads the model from the inserted row.
+    /// Updating a row that is gone fails with `ModelNotFound`.
+    ///
+    /// Models with an `Original` field only update their dirty columns. Models with a `VERSION` column only update
+    /// the row when its version is still the one the model has, otherwise `save` fails with `StaleModel`.
+    async fn save<E>(&mut self, db: &Database<E>) -> Result<()>
+    where
+        E: Executor,
+        Self: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let transaction = observer::begin::<E, Self>(db).await?;
+        let mut query = Self::query(transaction.as_deref().unwrap_or(db));
+
+        let saved = query.save(self).await;
+
+        drop(query);
+
+        return observer::finish(transaction, saved).await;
+    }
+
+    /// Deletes the row, or soft deletes it when the model has soft deletes.
+    async fn delete<E: Executor>(&self, db: &Database<E>) -> Result<()> {
+        return delete(self, db, false).await;
+    }
+
+    async fn force_delete<E: Executor>(&self, db: &Database<E>) -> Result<()> {
+        return delete(self, db, true).await;
+    }
+
+    /// Restores a soft deleted model and reloads it.
+    async fn restore<E>(&mut self, db: &Database<E>) -> Result<()>
+    where
+        E: Executor,
+        Self: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let transaction = observer::begin::<E, Self>(db).await?;
+        let restored: Result<()> = async {
+            let db = transaction.as_deref().unwrap_or(db);
+            let mut query = Self::query(db);
+
+            query.r#where(Self::PRIMARY_KEY, "=", <E::T as Driver>::Argument::from(self.key()?)).restore().await?;
+
+            drop(query);
+
+            self.refresh(db).await?;
+
+            for observer in observer::observers::<Self>() {
+                observer.restored(self)?;
+            }
+
+            return Ok(());
+        }.await;
+
+        return observer::finish(transaction, restored).await;
+    }
+
+    /// The `R` rows whose `foreign_key` holds this model's primary key.
+    ///
+    /// ```ignore
+    /// let orders = user.has_many::<Order, _>(&db, "user_uuid").all().await?;
+    /// ```
+    fn has_many<'q, R: Model, E: Executor>(&self, db: &'q Database<E>, foreign_key: &str) -> Query<'q, E, R> {
+        let mut query = R::query(db);
+
+        query.where_value(&format!("{}.{}", R::TABLE, foreign_key), self.key());
+
+        return query;
+    }
+
+    /// Same as `has_many` limited to one row, get it with `first`.
+    fn has_one<'q, R: Model, E: Executor>(&self, db: &'q Database<E>, foreign_key: &str) -> Query<'q, E, R> {
+        let mut query = self.has_many::<R, E>(db, foreign_key);
+
+        query.limit(1);
+
+        return query;
+    }
+
+    /// The `R` row whose primary key is held by this model's `foreign_key`, get it with `first`.
+    fn belongs_to<'q, R: Model, E: Executor>(&self, db: &'q Database<E>, foreign_key: &str) -> Query<'q, E, R> {
+        let mut query = R::query(db);
+
+        query.where_value(&format!("{}.{}", R::TABLE, R::PRIMARY_KEY), self.value(foreign_key));
+        query.limit(1);
+
+        return query;
+    }
+
+    /// The `R` rows linked to this model through the `pivot` table.
+    ///
+    /// ```ignore
+    /// let roles = user.belongs_to_many::<Role, _>(&db, "user_roles", "user_uuid", "role_uuid").all().await?;
+    /// ```
+    fn belongs_to_many<'q, R: Model, E: Executor>(&self, db: &'q Database<E>, pivot: &str, foreign_pivot_key: &str, related_pivot_key: &str) -> BelongsToMany<'q, E, R> {
+        return BelongsToMany::new(db, self.key(), pivot, foreign_pivot_key, related_pivot_key);
+    }
+
+    /// The `R` rows whose `{name}_type` and `{name}_id` columns point at this model.
+    ///
+    /// ```ignore
+    /// let comments = post.morph_many::<Comment, _>(&db, "commentable").all().await?;
+    /// ```
+    fn morph_many<'q, R: Model, E: Executor>(&self, db: &'q Database<E>, name: &str) -> Query<'q, E, R> {
+        let mut query = R::query(db);
+
+        query.r#where(&format!("{}.{}_type", R::TABLE, name), "=", <E::T as Driver>::Argument::from(Value::from(Self::MORPH_NAME)));
+        query.where_value(&format!("{}.{}_id", R::TABLE, name), self.key());
+
+        return query;
+    }
+
+    /// Same as `morph_many` limited to one row, get it with `first`.
+    fn morph_one<'q, R: Model, E: Executor>(&self, db: &'q Database<E>, name: &str) -> Query<'q, E, R> {
+        let mut query = self.morph_many::<R, E>(db, name);
+
+        query.limit(1);
+
+        return query;
+    }
+
+    /// The model this model's `{name}_type` and `{name}_id` columns point at, of a type registered with `register_morph`.
+    ///
+    /// ```ignore
+    /// let commentable = comment.morph_to(&db, "commentable").await?;
+    /// let post = commentable.as_ref().and_then(|commentable| commentable.downcast_ref::<Post>());
+    /// ```
+    async fn morph_to<E: Executor>(&self, db: &Database<E>, name: &str) -> Result<Option<relation::Morphed>> {
+        return relation::morph_to(&db.executor, name, self.value(&format!("{}_type", name))?, self.value(&format!("{}_id", name))?).await;
+    }
+
+    /// Lets `morph_to` load this model from rows whose `{name}_type` is `MORPH_NAME`.
+    fn register_morph<E: Executor>() where Self: Sync + Decodable {
+        relation::register_morph::<E, Self>();
+    }
+
+    /// Whether the model's `deleted_at` is set.
+    fn trashed(&self) -> bool {
+        return Self::SOFT_DELETES && self.value(Self::DELETED_AT).is_ok_and(|value| !value.is_null());
+    }
+
+    /// Reloads the model from its row.
+    async fn refresh<E>(&mut self, db: &Database<E>) -> Result<()>
+    where
+        E: Executor,
+        Self: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let mut query = Self::query(db);
+
+        *self = query.with_trashed().find_or_fail(self.key()?).await?;
+
+        return Ok(());
+    }
+}
+
+/// `Model::query` on an executor, for code that has no `Database`.
+pub(crate) fn query<'q, E: Executor, M: Model>(executor: &'q E) -> Query<'q, E, M> {
+    let mut query = Query::new(M::TABLE, executor);
+
+    query.statement.query.primary_key = Some(M::PRIMARY_KEY.to_string());
+
+    if M::TIMESTAMPS {
+        query.timestamps = Some((M::CREATED_AT, M::UPDATED_AT));
+    }
+
+    if M::SOFT_DELETES {
+        query.soft_deletes = Some(M::DELETED_AT);
+    }
+
+    query.blind_indexes = M::BLIND_INDEXES;
+
+    if M::KEY_TYPE != KeyType::Increment {
+        query.generated_key = Some((M::PRIMARY_KEY, M::KEY_TYPE));
+    }
+
+    query.version = M::VERSION.map(|column| (M::TABLE, column));
+    query.cast_values = Some(M::cast_values);
+
+    if !M::FILLABLE.is_empty() || !M::GUARDED.is_empty() {
+        query.guard = Some(Guard {
+            table: M::TABLE,
+            fillable: M::FILLABLE,
+            guarded: M::GUARDED,
+            reject: M::REJECT_GUARDED,
+        });
+    }
+
+    if let Some(scopes) = GLOBAL_SCOPES.read().unwrap().get(&(TypeId::of::<M>(), TypeId::of::<E>())) {
+        query.scopes = scopes.iter()
+            .filter_map(|(name, scope)| scope.downcast_ref::<Scope<E, M>>().map(|scope| (*name, scope.clone())))
+            .collect();
+    }
+
+    return query;
+}
+
+/// `Model::delete` and `force_delete`, running the `deleting` and `deleted` hooks.
+async fn delete<E: Executor, M: Model>(model: &M, db: &Database<E>, force: bool) -> Result<()> {
+    let transaction = observer::begin::<E, M>(db).await?;
+    let deleted: Result<()> = async {
+        let observers = observer::observers::<M>();
+        let mut query = M::query(transaction.as_deref().unwrap_or(db));
+
+        for observer in &observers {
+            observer.deleting(model)?;
+        }
+
+        query.r#where(M::PRIMARY_KEY, "=", <E::T as Driver>::Argument::from(model.key()?));
+
+        match force {
+            true => query.force_delete().await?,
+            false => query.delete().await?,
+        }
+
+        for observer in &observers {
+            observer.deleted(model)?;
+        }
+
+        return Ok(());
+    }.await;
+
+    return observer::finish(transaction, deleted).await;
+}
+
+/// How the primary key of a new row is made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// Filled in by the database, like an auto increment column.
+    Increment,
+    /// A random UUID v4.
+    Uuid,
+    /// A time ordered UUID v7, which keeps indexes on the key compact.
+    UuidV7,
+    /// A time ordered ULID, stored as its 26 character text.
+    Ulid,
+}
+
+impl KeyType {
+    /// A new key, `None` for keys filled in by the database.
+    pub fn generate(&self) -> Option<Value> {
+        return match self {
+            KeyType::Increment => None,
+            KeyType::Uuid => Some(Value::Uuid(Uuid::new_v4())),
+            KeyType::UuidV7 => Some(Value::Uuid(Uuid::now_v7())),
+            KeyType::Ulid => Some(Value::Text(Ulid::new().to_string())),
+        };
+    }
+}
+
+/// Whether a primary key holds a value, `NULL`, `0` and `""` count as unset.
+fn is_set(key: &Value) -> bool {
+    return match key {
+        Value::Null => false,
+        Value::Int(0) => false,
+        Value::Text(key) => !key.is_empty(),
+        Value::Uuid(key) => !key.is_nil(),
+        _ => true,
+    };
+}
+
+/// Returned by the `*_or_fail` methods and `save` when no row matched, get it back with `error.downcast_ref::<ModelNotFound>()`.
+#[derive(Debug, Clone)]
+pub struct ModelNotFound {
+    pub table: &'static str,
+}
+
+impl fmt::Display for ModelNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "no row found in `{}`", self.table);
+    }
+}
+
+impl std::error::Error for ModelNotFound {}
+
+/// Returned by `save` and `Update::versioned` when the row was updated since the model was read, so its version no longer matches.
+#[derive(Debug, Clone)]
+pub struct StaleModel {
+    pub table: &'static str,
+    /// The primary key of the saved model, `Null` for `Update::versioned`.
+    pub key: Value,
+}
+
+impl fmt::Display for StaleModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self.key {
+            Value::Null => write!(f, "the rows of `{}` were updated by someone else", self.table),
+            _ => write!(f, "row {:?} of `{}` was updated by someone else", self.key, self.table),
+        };
+    }
+}
+
+impl std::error::Error for StaleModel {}
+
+/// Returned when a model with `REJECT_GUARDED` is given a column it does not allow to be mass assigned.
+#[derive(Debug, Clone)]
+pub struct MassAssignment {
+    pub table: &'static str,
+    pub column: String,
+}
+
+impl fmt::Display for MassAssignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "`{}` can't be mass assigned on `{}`", self.column, self.table);
+    }
+}
+
+impl std::error::Error for MassAssignment {}
+
+/// The `FILLABLE` and `GUARDED` columns of a model, checked by the queries taking values from a client.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Guard {
+    pub table: &'static str,
+    pub fillable: &'static [&'static str],
+    pub guarded: &'static [&'static str],
+    pub reject: bool,
+}
+
+impl Guard {
+    pub(crate) fn allows(&self, column: &str) -> bool {
+        return (self.fillable.is_empty() || self.fillable.contains(&column)) && !self.guarded.contains(&column);
+    }
+
+    pub(crate) fn filter(&self, values: Vec<(String, Value)>) -> Result<Vec<(String, Value)>> {
+        if let (true, Some((column, _))) = (self.reject, values.iter().find(|(column, _)| !self.allows(column))) {
+            return Err(MassAssignment { table: self.table, column: column.clone() }.into());
+        }
+
+        return Ok(values.into_iter().filter(|(column, _)| self.allows(column)).collect());
+    }
+}
+
+/// The values a model was loaded with, add it as a `#[sqlx(skip)]` field to enable dirty tracking.
+///
+/// ```ignore
+/// #[derive(sqlx::FromRow, Model)]
+/// pub struct User {
+///     pub id: i64,
+///     pub email: String,
+///     #[sqlx(skip)]
+///     pub original: Original,
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Original(Option<Vec<(&'static str, Value)>>);
+
+impl <'q, E, M>Query<'q, E, M>
+where
+    E: Executor,
+    M: Model
+{
+    /// `column = value` for a value read from a model, failing the query when a cast couldn't encode it.
+    pub(crate) fn where_value(&mut self, column: &str, value: Result<Value>) -> &mut Self {
+        return match value {
+            Ok(value) => self.r#where(column, "=", <E::T as Driver>::Argument::from(value)),
+            Err(error) => self.fail(error),
+        };
+    }
+
+    /// Rows with at least one related `R` the constraint matches, as an `EXISTS` subquery.
+    ///
+    /// ```ignore
+    /// User::query(&db).where_has("orders", |query: &mut Query<SQLite, Order>| {
+    ///     query.r#where("orders.paid", "=", false);
+    /// });
+    /// ```
+    pub fn where_has<R, F>(&mut self, relation: &str, constraint: F) -> &mut Self
+    where
+        R: Model,
+        F: FnOnce(&mut Query<'_, E, R>)
+    {
+        if let Some((sql, arguments)) = self.related::<R, F>(relation, "1", constraint) {
+            self.statement.query.and_where_raw(&format!("EXISTS ({})", sql));
+            self.statement.arguments.extend(arguments);
+        }
+
+        return self;
+    }
+
+    /// Rows without a related `R` the constraint matches, as a `NOT EXISTS` subquery.
+    pub fn where_doesnt_have<R, F>(&mut self, relation: &str, constraint: F) -> &mut Self
+    where
+        R: Model,
+        F: FnOnce(&mut Query<'_, E, R>)
+    {
+        if let Some((sql, arguments)) = self.related::<R, F>(relation, "1", constraint) {
+            self.statement.query.and_where_raw(&format!("NOT EXISTS ({})", sql));
+            self.statement.arguments.extend(arguments);
+        }
+
+        return self;
+    }
+
+    /// Selects the number of related `R` rows as `{relation}_count`, read it into an `#[orm(computed)]` field.
+    pub fn with_count<R: Model>(&mut self, relation: &str) -> &mut Self {
+        return self.with_count_where::<R, _>(relation, |_| {});
+    }
+
+    /// Same as `with_count`, counting only the related rows the constraint matches.
+    pub fn with_count_where<R, F>(&mut self, relation: &str, constraint: F) -> &mut Self
+    where
+        R: Model,
+        F: FnOnce(&mut Query<'_, E, R>)
+    {
+        let Some((sql, arguments)) = self.related::<R, F>(relation, "COUNT(*)", constraint) else {
+            return self;
+        };
+
+        if self.statement.query.select.len() == 0 {
+            self.statement.query.select.push("*".to_string());
+        }
+
+        self.statement.query.select.push(format!("({}) AS {}_count", sql, relation));
+        self.statement.arguments.select(arguments);
+
+        return self;
+    }
+
+    /// A subquery on `R` correlated to the rows of this query and its values.
+    ///
+    /// An unknown relation is kept as the error the query fails with when it runs, like one given to `with`.
+    fn related<R, F>(&mut self, name: &str, select: &str, constraint: F) -> Option<(String, Arguments<'q, E::T>)>
+    where
+        R: Model,
+        F: FnOnce(&mut Query<'_, E, R>)
+    {
+        let Some(relation) = M::relation(name).filter(|relation| relation.table().is_none_or(|table| table == R::TABLE)) else {
+            self.statement.arguments.fail(anyhow::anyhow!("`{}` has no relation `{}` to `{}`", M::TABLE, name, R::TABLE));
+
+            return None;
+        };
+
+        let mut query = query::<E, R>(self.db);
+
+        query.select(vec![select]);
+
+        relation.correlate::<E, M, R>(&mut query);
+
+        query.scope(constraint);
+        query.apply_scopes();
+
+        return match self.db.to_sql(&query.statement) {
+            Ok(sql) => Some((sql, query.statement.arguments)),
+            Err(error) => {
+                self.statement.arguments.fail(error);
+
+                None
+            }
+        };
+    }
+}
+
+impl <'q, E, M>Query<'q, E, M>
+where
+    E: Executor,
+    M: Model + for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+{
+    pub async fn find<K: Into<Value>>(&mut self, id: K) -> Result<Option<M>> {
+        return self.r#where(M::PRIMARY_KEY, "=", <E::T as Driver>::Argument::from(id.into())).first().await;
+    }
+
+    pub async fn find_or_fail<K: Into<Value>>(&mut self, id: K) -> Result<M> {
+        return self.r#where(M::PRIMARY_KEY, "=", <E::T as Driver>::Argument::from(id.into())).first_or_fail().await;
+    }
+
+    pub async fn first(&mut self) -> Result<Option<M>> {
+        self.statement.query.limit = Some(1);
+
+        return Ok(self.all().await?.into_iter().next());
+    }
+
+    pub async fn first_or_fail(&mut self) -> Result<M> {
+        return self.first().await?.ok_or_else(|| ModelNotFound { table: M::TABLE }.into());
+    }
+
+    pub async fn all(&mut self) -> Result<Vec<M>> {
+        self.apply_scopes();
+
+        let mut models = self.db.all::<M>(&self.statement).await?;
+
+        for model in &mut models {
+            model.sync_original()?;
+        }
+
+        relation::load(self.db, &mut models, &self.eager).await?;
+
+        return Ok(models);
+    }
+
+    pub async fn paginate(&mut self, limit: u64, page: u64) -> Result<Pagination<M>> {
+        self.statement.query.limit = Some(limit);
+        self.statement.query.page = Some(page);
+
+        self.apply_scopes();
+
+        let mut pagination = self.db.paginate::<M>(&self.statement).await?;
+
+        for model in &mut pagination.items {
+            model.sync_original()?;
+        }
+
+        relation::load(self.db, &mut pagination.items, &self.eager).await?;
+
+        return Ok(pagination);
+    }
+
+    /// Runs the `saving`, `creating` and `created` hooks of the model's observers around the insert.
+    ///
+    /// The columns the model does not allow to be mass assigned are dropped or rejected, see `Model::FILLABLE`.
+    pub async fn create<I, V: IntoValues<I>>(&mut self, values: V) -> Result<M> {
+        let values = self.fill(values)?;
+
+        return self.insert_model(values).await;
+    }
+
+    async fn insert_model(&mut self, values: Vec<(String, Value)>) -> Result<M> {
+        let observers = observer::observers::<M>();
+        let mut attributes = Attributes(values);
+
+        for observer in &observers {
+            observer.saving(&mut attributes)?;
+            observer.creating(&mut attributes)?;
+        }
+
+        self.bind_values(attributes.0, false, true)?;
+
+        let mut model = self.db.insert_as::<M>(&self.statement).await?;
+
+        model.sync_original()?;
+
+        for observer in &observers {
+            observer.created(&model)?;
+        }
+
+        return Ok(model);
+    }
+
+    /// Same as `Model::save` without its transaction, use it to `touch(false)` a save.
+    pub async fn save(&mut self, model: &mut M) -> Result<()> {
+        if !model.exists() {
+            let values = model.values()?
+                .into_iter()
+                .filter(|(column, value)| *column != M::PRIMARY_KEY || is_set(value))
+                .map(|(column, value)| (column.to_string(), value))
+                .collect::<Vec<_>>();
+
+            *model = self.insert_model(values).await?;
+
+            return Ok(());
+        }
+
+        let dirty = model.dirty_fields();
+        let values = model.values()?
+            .into_iter()
+            .filter(|(column, _)| *column != M::PRIMARY_KEY && dirty.contains(column))
+            .collect::<Vec<_>>();
+
+        if values.len() == 0 {
+            return Ok(());
+        }
+
+        let observers = observer::observers::<M>();
+        let mut attributes = Attributes(values.into_iter().map(|(column, value)| (column.to_string(), value)).collect());
+
+        for observer in &observers {
+            observer.saving(&mut attributes)?;
+            observer.updating(&mut attributes)?;
+        }
+
+        for (column, value) in attributes.iter().filter(|(column, _)| M::COLUMNS.contains(column)) {
+            if model.value(column)? != *value {
+                model.set_value(column, value.clone())?;
+            }
+        }
+
+        // Columns a hook removed aren't written, they keep their original value and stay dirty.
+        let unwritten = dirty.into_iter().filter(|column| attributes.get(column).is_none()).collect::<Vec<_>>();
+
+        if attributes.0.len() == 0 {
+            return Ok(());
+        }
+
+        let key = model.key()?;
+        let version = M::VERSION.map(|column| model.value(column).map(|value| (column, value))).transpose()?;
+
+        let touched = self.bind_values(attributes.0, false, false)?;
+
+        self.r#where(M::PRIMARY_KEY, "=", <E::T as Driver>::Argument::from(key.clone()));
+
+        if let Some((column, version)) = &version {
+            self.and_where(column, "=", <E::T as Driver>::Argument::from(version.clone()));
+        }
+
+        let affected = self.db.update(&self.statement).await?;
+
+        // A key set on a model without an `Original` field looks like an existing row, the update shows it isn't.
+        if affected == 0 && version.is_none() {
+            return Err(ModelNotFound { table: M::TABLE }.into());
+        }
+
+        if let Some((column, version)) = version {
+            if affected == 0 {
+                return Err(StaleModel { table: M::TABLE, key: key }.into());
+            }
+
+            if let Value::Int(version) = version {
+                model.set_value(column, Value::Int(version + 1))?;
+            }
+        }
+
+        for (column, value) in touched {
+            model.set_value(&column, value)?;
+        }
+
+        let original = model.original().map(|original| original.to_vec());
+
+        model.sync_original()?;
+
+        if let (Some(original), Some(Original(Some(values)))) = (original, model.snapshot_mut()) {
+            for (column, value) in values.iter_mut().filter(|(column, _)| unwritten.contains(column)) {
+                if let Some((_, previous)) = original.iter().find(|(c, _)| c == column) {
+                    *value = previous.clone();
+                }
+            }
+        }
+
+        for observer in &observers {
+            observer.updated(model)?;
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,232 @@
+use std::vec;
+
+use anyhow::{Ok, Result};
+
+use sqlx::MySql;
+
+use crate::{Driver, query::{QueryBuilder, QueryStatement, WhereQuery}};
+
+#[derive(Debug)]
+pub(crate) struct Builder<'q> {
+    statement: &'q QueryStatement,
+}
+
+impl <'q>QueryBuilder<'q> for Builder<'q> {
+    fn new(statement: &'q QueryStatement) -> Self {
+        return Self {
+            statement: statement
+        };
+    }
+
+    fn insert(&self) -> Result<String> {
+        let columns = self.statement.columns.clone().unwrap();
+
+        // MySQL matches the row on any unique index, a column set to itself leaves a matched row as it is.
+        let upsert = match &self.statement.upsert {
+            Some((unique_by, update)) if update.len() == 0 => format!(" ON DUPLICATE KEY UPDATE {} = {}", unique_by[0], unique_by[0]),
+            Some((_, update)) => format!(
+                " ON DUPLICATE KEY UPDATE {}",
+                update.iter().map(|column| format!("{} = VALUES({})", column, column)).collect::<Vec<_>>().join(", ")
+            ),
+            None => String::new(),
+        };
+
+        return Ok(format!(
+            "INSERT INTO {} ({}) VALUES ({}){};",
+            self.statement.table,
+            columns.join(", "),
+            std::iter::repeat_n("?", columns.len()).collect::<Vec<_>>().join(", "),
+            upsert
+        ));
+    }
+    
+    fn update(&self) -> Result<String> {
+        let mut sql = vec![
+            format!("UPDATE {}", self.statement.table),
+            format!("SET {}", self.statement
+                .columns
+                .clone()
+                .unwrap()
+                .iter()
+                .map(|f| format!("{} = ?", f))
+                .chain(self.statement.raw_columns.iter().map(|(column, expression)| format!("{} = {}", column, expression)))
+                .collect::<Vec<_>>()
+                .join(", ")
+            )
+        ];
+
+        if self.statement.where_queries.len() != 0 {
+            sql.extend([
+                "WHERE".to_string(),
+                    self.r#where().unwrap(),
+            ]);
+        }
+
+        return Ok(sql.join(" "));
+    }
+    
+    fn delete(&self) -> Result<String> {
+        let mut sql = vec![format!("DELETE FROM {}", self.statement.table)];
+
+        if self.statement.where_queries.len() != 0 {
+            sql.extend([
+                "WHERE".to_string(),
+                    self.r#where().unwrap(),
+            ]);
+        }
+
+        return Ok(sql.join(" "));
+    }
+
+    fn query(&self) -> Result<String> {
+        let mut sql = vec![
+            "SELECT".to_string(),
+                self.select().unwrap(),
+            "FROM".to_string(),
+                self.statement.table.clone(),
+        ];
+
+        if self.statement.join.len() != 0 {
+            sql.push(self.join().unwrap());
+        }
+
+        if self.statement.where_queries.len() != 0 {
+            sql.extend([
+                "WHERE".to_string(),
+                    self.r#where().unwrap(),
+            ]);
+        }
+
+        if self.statement.order_by.len() != 0 {
+            sql.extend([
+                "ORDER BY".to_string(),
+                    self.order_by().unwrap(),
+            ]);
+        }
+
+        if let Some(limit) = self.statement.limit {
+            sql.push(format!("LIMIT {}", limit));
+
+            if let Some(page) = self.statement.page.filter(|page| *page > 1) {
+                sql.push(format!("OFFSET {}", (page - 1) * limit));
+            }
+        }
+
+        if let Some(lock) = self.statement.lock.as_ref().and_then(MySql::lock) {
+            sql.push(lock);
+        }
+
+        return Ok(sql.join(" "));
+    }
+
+    fn select(&self) -> Result<String> {
+        if self.statement.select.len() == 0 {
+            return Ok(String::from("*"));
+        }
+
+        return Ok(self.statement.select.join(", "));
+    }
+
+    fn join(&self) -> Result<String> {
+        let mut conditions: Vec<String> = Vec::new();
+
+        for join in &self.statement.join {
+            match join.join_type {
+                crate::query::JoinType::LeftJoin => conditions.push(format!("LEFT JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::RightJoin => conditions.push(format!("RIGHT JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::InnerJoin => conditions.push(format!("INNER JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::FullOuterJoin => conditions.push(format!("FULL OUTER JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::CrossJoin => conditions.push(format!("CROSS JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+            }
+        }
+
+        return Ok(conditions.join(" "));
+    }
+
+    fn r#where(&self) -> Result<String> {
+        return self.conditions(&self.statement.where_queries);
+    }
+
+    fn order_by(&self) -> Result<String> {
+        return Ok(self.statement.order_by
+            .iter()
+            .map(|order| match order.order {
+                crate::query::Order::ASC => format!("{} ASC", order.column),
+                crate::query::Order::DESC => format!("{} DESC", order.column),
+            })
+            .collect::<Vec<_>>()
+            .join(", "));
+    }
+    
+    fn group_by(&self) -> Result<String> {
+        todo!()
+    }
+    
+}
+
+impl <'q>Builder<'q> {
+    fn conditions(&self, where_queries: &[WhereQuery]) -> Result<String> {
+        let mut conditions: Vec<String> = Vec::new();
+
+        for where_query in where_queries {
+            if let Some(position) = &where_query.position {
+                match position {
+                    crate::query::QueryPosition::AND => conditions.push(String::from("AND")),
+                    crate::query::QueryPosition::OR => conditions.push(String::from("OR")),
+                }
+            }
+
+            match &where_query.group {
+                Some(group) => conditions.push(format!("({})", self.conditions(group).unwrap())),
+                None => {
+                    match where_query.operator.clone().unwrap().as_str().to_lowercase().as_str() {
+                        "like" => conditions.push(format!("{} LIKE CONCAT('%', ?, '%')", where_query.column.clone().unwrap())),
+                        "is null" => conditions.push(format!("{} IS NULL", where_query.column.clone().unwrap())),
+                        "is not null" => conditions.push(format!("{} IS NOT NULL", where_query.column.clone().unwrap())),
+                        "raw" => conditions.push(where_query.column.clone().unwrap()),
+                        "in" => conditions.push(match where_query.values.unwrap_or(0) {
+                            0 => String::from("1 = 0"),
+                            values => format!("{} IN ({})", where_query.column.clone().unwrap(), vec!["?"; values].join(", ")),
+                        }),
+                        _ => conditions.push(format!("{} {} ?", where_query.column.clone().unwrap(), where_query.operator.clone().unwrap())),
+                    }
+                },
+            }
+        }
+
+        return Ok(conditions.join(" "));
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::query::{Lock, LockMode, LockWait, QueryBuilder, QueryStatement};
+
+    use super::Builder;
+
+    #[test]
+    fn concatenates_like_patterns() {
+        let mut statement = QueryStatement::new("users");
+
+        statement.and_where("name", "like");
+        statement.limit = Some(10);
+
+        assert_eq!(Builder::new(&statement).query().unwrap(), "SELECT * FROM users WHERE name LIKE CONCAT('%', ?, '%') LIMIT 10");
+    }
+
+    #[test]
+    fn ends_selects_with_the_lock() {
+        let locked = |mode, wait| {
+            let mut statement = QueryStatement::new("jobs");
+
+            statement.and_where("status", "=");
+            statement.lock = Some(Lock { mode: mode, wait: wait });
+
+            return Builder::new(&statement).query().unwrap();
+        };
+
+        assert_eq!(locked(LockMode::Update, LockWait::Wait), "SELECT * FROM jobs WHERE status = ? FOR UPDATE");
+        assert_eq!(locked(LockMode::Update, LockWait::SkipLocked), "SELECT * FROM jobs WHERE status = ? FOR UPDATE SKIP LOCKED");
+        assert_eq!(locked(LockMode::Share, LockWait::Wait), "SELECT * FROM jobs WHERE status = ? FOR SHARE");
+        assert_eq!(locked(LockMode::Share, LockWait::NoWait), "SELECT * FROM jobs WHERE status = ? FOR SHARE NOWAIT");
+    }
+}
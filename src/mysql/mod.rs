@@ -1,91 +1,262 @@
 mod builder;
+mod value;
+
+use std::sync::Arc;
 
 use anyhow::Result;
-use sqlx::{FromRow, MySql, MySqlPool, Pool};
+use async_stream::try_stream;
+use futures::{TryStreamExt, stream::BoxStream};
+use sqlx::{Arguments, FromRow, MySql, MySqlPool, Pool};
+use tokio::sync::Mutex;
 
-use crate::{Executor, query::{Pagination, Statement}};
+use crate::{Driver, Executor, Value, mysql::builder::Builder, query::{Connection, Pagination, QueryBuilder, SharedTransaction, Statement, WhereQuery}};
 
+#[derive(Debug)]
 pub struct MySQL {
     db: Pool<MySql>,
+    transaction: Option<SharedTransaction<MySql>>,
+    /// The savepoint of a transaction begun in another, `0` for the outermost.
+    depth: usize,
 }
 
 impl MySQL {
     pub async fn connect(url: &str) -> Result<Self> {
         return Ok(Self {
-            db: MySqlPool::connect(url).await.unwrap()
+            db: MySqlPool::connect(url).await?,
+            transaction: None,
+            depth: 0,
         });
     }
+
+    async fn connection(&self) -> Result<Connection<'_, MySql>> {
+        return Connection::acquire(&self.db, &self.transaction).await;
+    }
 }
 
 impl Executor for MySQL {
     type T = MySql;
 
     async fn new(url: &str) -> Self where Self: Sized {
-        todo!()
+        return Self::connect(url).await.unwrap();
     }
 
     fn db<'q>(&'q self) -> &'q Pool<Self::T> {
-        todo!()
+        return &self.db;
+    }
+
+    async fn begin(&self) -> Result<Self> where Self: Sized {
+        if let Some(transaction) = &self.transaction {
+            let nested = Self {
+                db: self.db.clone(),
+                transaction: Some(transaction.clone()),
+                depth: self.depth + 1,
+            };
+
+            nested.execute(&format!("SAVEPOINT flyer_orm_{}", nested.depth)).await?;
+
+            return Ok(nested);
+        }
+
+        return Ok(Self {
+            db: self.db.clone(),
+            transaction: Some(Arc::new(Mutex::new(Some(self.db.begin().await?)))),
+            depth: 0,
+        });
     }
 
-    fn to_sql<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<String> {
-        todo!()
+    async fn commit(&self) -> Result<()> {
+        if self.depth > 0 {
+            return self.execute(&format!("RELEASE SAVEPOINT flyer_orm_{}", self.depth)).await;
+        }
+
+        if let Some(transaction) = &self.transaction
+            && let Some(transaction) = transaction.lock().await.take()
+        {
+            transaction.commit().await?;
+        }
+
+        return Ok(());
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        if self.depth > 0 {
+            self.execute(&format!("ROLLBACK TO SAVEPOINT flyer_orm_{}", self.depth)).await?;
+
+            return self.execute(&format!("RELEASE SAVEPOINT flyer_orm_{}", self.depth)).await;
+        }
+
+        if let Some(transaction) = &self.transaction
+            && let Some(transaction) = transaction.lock().await.take()
+        {
+            transaction.rollback().await?;
+        }
+
+        return Ok(());
+    }
+
+    fn to_sql<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<String> {
+        return Builder::new(&statement.query).query();
     }
 
     async fn execute<'q>(&self, sql: &'q str) -> Result<()> {
-        todo!();
+        sqlx::raw_sql(sql)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        return Ok(());
     }
-    
-    async fn insert<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()> {
-        todo!()
+
+    async fn insert<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<()> {
+        sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).insert()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        return Ok(());
     }
-    
-    async fn insert_as<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<O>
+
+    /// Reads the inserted row back by its primary key, the generated key or else `LAST_INSERT_ID()`.
+    async fn insert_as<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<O>
     where
         O: for<'r> sqlx::FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        todo!()
+        let query_result = sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).insert()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?;
+
+        let (key, value) = match &statement.query.key {
+            Some((key, value)) => (key.clone(), value.clone()),
+            None => (statement.query.primary_key.clone().unwrap_or("id".to_string()), Value::Int(query_result.last_insert_id() as i64)),
+        };
+        let mut statement = Statement::<Self::T>::new(&statement.query.table);
+
+        statement.query.where_queries.push(WhereQuery {
+            column: Some(key),
+            operator: Some("=".to_string()),
+            position: None,
+            group: None,
+            values: None
+        });
+
+        Self::T::bind(&mut statement.arguments, value)?;
+
+        return self.first(&statement).await;
     }
-    
-    async fn update<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()> {
-        todo!()
+
+    async fn update<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<u64> {
+        return Ok(sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).update()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?
+            .rows_affected());
     }
-    
-    async fn count<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<u64> {
-        return Ok(0);
+
+    async fn count<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<u64> {
+        let mut count = statement.duplicate();
+
+        count.query.order_by = Vec::new();
+        count.query.limit = None;
+        count.query.page = None;
+        count.query.lock = None;
+
+        // Counted over the query as a subquery, so values bound in its select, like `with_count`, keep their place.
+        let sql = format!("SELECT COUNT(*) as total FROM ({}) AS counted", self.to_sql(&count)?);
+
+        return Ok(sqlx::query_scalar_with::<Self::T, i64, _>(&sql, count.arguments.build()?)
+            .fetch_one(&mut *self.connection().await?)
+            .await? as u64);
     }
-    
-    async fn delete<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()> {
-        todo!()
+
+    async fn delete<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<()> {
+        sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).delete()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        return Ok(());
     }
 
     async fn query_all<'q, O, T: 'q + sqlx::Encode<'q, Self::T> + sqlx::Type<Self::T>>(&self, sql: &str, args: Vec<T>) -> Result<Vec<O>>
     where
-        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized {
-        todo!()
+        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        let mut arguments: <Self::T as sqlx::Database>::Arguments<'q> = Default::default();
+
+        for arg in args {
+            arguments.add(arg).map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(sql, arguments)
+            .fetch_all(&mut *self.connection().await?)
+            .await?);
     }
 
     async fn query_one<'q, O, T: 'q + sqlx::Encode<'q, Self::T> + sqlx::Type<Self::T>>(&self, sql: &str, args: Vec<T>) -> Result<O>
     where
-        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized {
-        todo!()
+        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        let mut arguments: <Self::T as sqlx::Database>::Arguments<'q> = Default::default();
+
+        for arg in args {
+            arguments.add(arg).map_err(|e| anyhow::anyhow!(e))?;
+        }
+
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(sql, arguments)
+            .fetch_one(&mut *self.connection().await?)
+            .await?);
+    }
+
+    async fn first<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<O>
+    where
+        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement)?, statement.arguments.build()?)
+            .fetch_one(&mut *self.connection().await?)
+            .await?);
     }
 
-    async fn all<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<Vec<O>>
+    async fn all<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<Vec<O>>
     where
-        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized {
-        todo!()
+        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement)?, statement.arguments.build()?)
+            .fetch_all(&mut *self.connection().await?)
+            .await?);
     }
 
-    async fn first<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<O>
+    async fn paginate<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<Pagination<O>>
     where
-        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized {
-        todo!()
+        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
+    {
+        let total = self.count(statement).await?;
+
+        let items = sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement)?, statement.arguments.build()?)
+            .fetch_all(&mut *self.connection().await?)
+            .await?;
+
+        return Ok(Pagination::new(items, total, statement.query.page.unwrap(), statement.query.limit.unwrap()));
     }
 
-    async fn paginate<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<Pagination<O>>
+    fn stream<'q, O>(&'q self, statement: &'q Statement<'q, Self::T>) -> BoxStream<'q, Result<O>>
     where
-        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized {
-        todo!()
+        O: for<'r> FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized + 'q
+    {
+        return Box::pin(try_stream! {
+            let sql = self.to_sql(statement)?;
+            let mut connection = self.connection().await?;
+
+            // A transaction is one connection, its rows are read up front so the stream doesn't hold it from other queries.
+            if let Connection::Transaction(_) = connection {
+                let rows = sqlx::query_as_with::<Self::T, O, _>(&sql, statement.arguments.build()?)
+                    .fetch_all(&mut *connection)
+                    .await?;
+
+                drop(connection);
+
+                for row in rows {
+                    yield row;
+                }
+            } else {
+                let mut rows = sqlx::query_as_with::<Self::T, O, _>(&sql, statement.arguments.build()?).fetch(&mut *connection);
+
+                while let Some(row) = rows.try_next().await? {
+                    yield row;
+                }
+            }
+        });
     }
-}
\ No newline at end of file
+}
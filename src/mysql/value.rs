@@ -0,0 +1,166 @@
+use anyhow::Result;
+use sqlx::{Decode, Encode, FromRow, MySql, Row, TypeInfo, ValueRef, encode::IsNull, error::BoxDynError, mysql::{MySqlRow, MySqlTypeInfo, MySqlValueRef}, types::Type};
+
+use crate::{Decodable, Driver, Value, query::{Lock, LockMode, LockWait}};
+
+impl Type<MySql> for Value {
+    fn type_info() -> MySqlTypeInfo {
+        return <str as Type<MySql>>::type_info();
+    }
+
+    fn compatible(_: &MySqlTypeInfo) -> bool {
+        return true;
+    }
+}
+
+/// Uuids are stored as hyphenated text, use `Value::Bytes` for `BINARY(16)` columns.
+impl<'q> Encode<'q, MySql> for Value {
+    fn encode_by_ref(&self, buf: &mut <MySql as sqlx::Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        return match self {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Bool(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Int(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Float(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Text(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Bytes(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Json(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Uuid(value) => Encode::<MySql>::encode_by_ref(&value.hyphenated(), buf),
+            Value::DateTime(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Date(value) => Encode::<MySql>::encode_by_ref(value, buf),
+            Value::Time(value) => Encode::<MySql>::encode_by_ref(value, buf),
+        };
+    }
+
+    fn produces(&self) -> Option<MySqlTypeInfo> {
+        return match self {
+            Value::Null => None,
+            Value::Bool(_) => Some(<bool as Type<MySql>>::type_info()),
+            Value::Int(_) => Some(<i64 as Type<MySql>>::type_info()),
+            Value::Float(_) => Some(<f64 as Type<MySql>>::type_info()),
+            Value::Bytes(_) => Some(<Vec<u8> as Type<MySql>>::type_info()),
+            Value::Json(_) => Some(<serde_json::Value as Type<MySql>>::type_info()),
+            Value::DateTime(_) => Some(<chrono::DateTime<chrono::Utc> as Type<MySql>>::type_info()),
+            Value::Date(_) => Some(<chrono::NaiveDate as Type<MySql>>::type_info()),
+            Value::Time(_) => Some(<chrono::NaiveTime as Type<MySql>>::type_info()),
+            Value::Text(_) | Value::Uuid(_) => Some(<str as Type<MySql>>::type_info()),
+        };
+    }
+}
+
+impl<'r> Decode<'r, MySql> for Value {
+    fn decode(value: MySqlValueRef<'r>) -> Result<Self, BoxDynError> {
+        if value.is_null() {
+            return Ok(Value::Null);
+        }
+
+        let name = value.type_info().name().to_string();
+
+        return Ok(match name.as_str() {
+            "BOOLEAN" => Value::Bool(<bool as Decode<MySql>>::decode(value)?),
+            "TINYINT" | "SMALLINT" | "INT" | "MEDIUMINT" | "BIGINT" => Value::Int(<i64 as Decode<MySql>>::decode(value)?),
+            "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "INT UNSIGNED" | "MEDIUMINT UNSIGNED" | "YEAR" => Value::Int(<u32 as Decode<MySql>>::decode(value)?.into()),
+            "BIGINT UNSIGNED" => Value::Int(<u64 as Decode<MySql>>::decode(value)?.try_into()?),
+            "FLOAT" | "DOUBLE" => Value::Float(<f64 as Decode<MySql>>::decode(value)?),
+            "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => Value::Bytes(<Vec<u8> as Decode<MySql>>::decode(value)?),
+            "JSON" => Value::Json(<serde_json::Value as Decode<MySql>>::decode(value)?),
+            "DATETIME" | "TIMESTAMP" => Value::DateTime(<chrono::DateTime<chrono::Utc> as Decode<MySql>>::decode(value)?),
+            "DATE" => Value::Date(<chrono::NaiveDate as Decode<MySql>>::decode(value)?),
+            "TIME" => Value::Time(<chrono::NaiveTime as Decode<MySql>>::decode(value)?),
+            _ => Value::Text(<String as Decode<MySql>>::decode(value)?),
+        });
+    }
+}
+
+/// A bound value, its bytes and type.
+#[derive(Clone)]
+pub struct Encoded {
+    bytes: Option<Vec<u8>>,
+    type_info: MySqlTypeInfo,
+}
+
+impl Type<MySql> for Encoded {
+    fn type_info() -> MySqlTypeInfo {
+        return <str as Type<MySql>>::type_info();
+    }
+
+    fn compatible(_: &MySqlTypeInfo) -> bool {
+        return true;
+    }
+}
+
+impl<'q> Encode<'q, MySql> for Encoded {
+    fn encode_by_ref(&self, buf: &mut Vec<u8>) -> Result<IsNull, BoxDynError> {
+        let Some(bytes) = &self.bytes else {
+            return Ok(IsNull::Yes);
+        };
+
+        buf.extend_from_slice(bytes);
+
+        return Ok(IsNull::No);
+    }
+
+    fn produces(&self) -> Option<MySqlTypeInfo> {
+        return Some(self.type_info.clone());
+    }
+}
+
+impl Driver for MySql {
+    type Argument = Value;
+    type Encoded<'q> = Encoded;
+
+    fn encode<'q, T: 'q + Encode<'q, Self> + Type<Self>>(value: T) -> Result<Encoded> {
+        let type_info = value.produces().unwrap_or_else(T::type_info);
+        let mut buf = Vec::new();
+
+        let bytes = match value.encode(&mut buf).map_err(|e| anyhow::anyhow!(e))? {
+            IsNull::Yes => None,
+            IsNull::No => Some(buf),
+        };
+
+        return Ok(Encoded { bytes: bytes, type_info: type_info });
+    }
+
+    /// Text is encoded as its bytes after a length encoded integer, which is 1, 3, 4 or 9 bytes long.
+    fn text<'q, T: Encode<'q, Self> + Type<Self>>(value: &T) -> Option<String> {
+        if !<str as Type<MySql>>::compatible(&value.produces().unwrap_or_else(T::type_info)) {
+            return None;
+        }
+
+        let mut buf = Vec::new();
+
+        if let IsNull::Yes = value.encode_by_ref(&mut buf).ok()? {
+            return None;
+        }
+
+        let prefix = match *buf.first()? {
+            0xfc => 3,
+            0xfd => 4,
+            0xfe => 9,
+            _ => 1,
+        };
+
+        return String::from_utf8(buf.get(prefix..)?.to_vec()).ok();
+    }
+
+    fn get(row: &MySqlRow, column: &str) -> Result<Value> {
+        return Ok(row.try_get::<Value, _>(column)?);
+    }
+
+    /// The MySQL 8 clauses, `FOR SHARE` replaces the `LOCK IN SHARE MODE` of older versions, which has no `SKIP LOCKED`.
+    fn lock(lock: &Lock) -> Option<String> {
+        let mode = match lock.mode {
+            LockMode::Update => "FOR UPDATE",
+            LockMode::Share => "FOR SHARE",
+        };
+
+        return Some(match lock.wait {
+            LockWait::Wait => mode.to_string(),
+            LockWait::SkipLocked => format!("{} SKIP LOCKED", mode),
+            LockWait::NoWait => format!("{} NOWAIT", mode),
+        });
+    }
+
+    fn decode<M: Decodable>(row: &MySqlRow) -> Result<M, sqlx::Error> {
+        return <M as FromRow<MySqlRow>>::from_row(row);
+    }
+}
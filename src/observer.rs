@@ -0,0 +1,140 @@
+use std::{any::{Any, TypeId}, collections::HashMap, sync::{Arc, LazyLock, RwLock}};
+
+use anyhow::Result;
+
+use crate::{Database, Executor, Model, Value, query::Transaction};
+
+/// Observers by model, the values are `Vec<Arc<dyn Observer<M>>>`.
+static OBSERVERS: LazyLock<RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Hooks run around the writes of a model, register them with `Model::observe`.
+///
+/// The hooks run before a write get the column values about to be written, the others get the model.
+/// An error returned by a hook aborts the operation, which runs in a transaction while the model is observed,
+/// a savepoint of the caller's when it is given a `Transaction`, rolled back on the error.
+///
+/// ```ignore
+/// struct UserObserver;
+///
+/// impl Observer<User> for UserObserver {
+///     fn creating(&self, attributes: &mut Attributes) -> Result<()> {
+///         if let Some(Value::Text(email)) = attributes.get("email") {
+///             attributes.set("email", email.trim().to_lowercase());
+///         }
+///
+///         return Ok(());
+///     }
+/// }
+///
+/// User::observe(UserObserver);
+/// ```
+pub trait Observer<M: Model>: Send + Sync + 'static {
+    /// Before `creating` and `updating`.
+    fn saving(&self, _attributes: &mut Attributes) -> Result<()> {
+        return Ok(());
+    }
+
+    fn creating(&self, _attributes: &mut Attributes) -> Result<()> {
+        return Ok(());
+    }
+
+    fn created(&self, _model: &M) -> Result<()> {
+        return Ok(());
+    }
+
+    /// Gets the dirty columns of the model.
+    fn updating(&self, _attributes: &mut Attributes) -> Result<()> {
+        return Ok(());
+    }
+
+    fn updated(&self, _model: &M) -> Result<()> {
+        return Ok(());
+    }
+
+    /// Before `delete` and `force_delete`.
+    fn deleting(&self, _model: &M) -> Result<()> {
+        return Ok(());
+    }
+
+    fn deleted(&self, _model: &M) -> Result<()> {
+        return Ok(());
+    }
+
+    fn restored(&self, _model: &M) -> Result<()> {
+        return Ok(());
+    }
+}
+
+/// The column values a model is about to be inserted or updated with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes(pub(crate) Vec<(String, Value)>);
+
+impl Attributes {
+    pub fn get(&self, column: &str) -> Option<&Value> {
+        return self.0.iter().find(|(name, _)| name == column).map(|(_, value)| value);
+    }
+
+    /// Sets the value of a column, adding the column when it is not written yet.
+    pub fn set<V: Into<Value>>(&mut self, column: &str, value: V) {
+        match self.0.iter_mut().find(|(name, _)| name == column) {
+            Some((_, current)) => *current = value.into(),
+            None => self.0.push((column.to_string(), value.into())),
+        }
+    }
+
+    /// Leaves the column out of the write.
+    pub fn remove(&mut self, column: &str) -> Option<Value> {
+        let index = self.0.iter().position(|(name, _)| name == column)?;
+
+        return Some(self.0.remove(index).1);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        return self.0.iter().map(|(column, value)| (column.as_str(), value));
+    }
+}
+
+pub(crate) fn observe<M: Model, O: Observer<M>>(observer: O) {
+    let observer: Arc<dyn Observer<M>> = Arc::new(observer);
+    let mut observers = OBSERVERS.write().unwrap();
+    let observers = observers.entry(TypeId::of::<M>()).or_insert_with(|| Box::new(Vec::<Arc<dyn Observer<M>>>::new()));
+
+    if let Some(observers) = observers.downcast_mut::<Vec<Arc<dyn Observer<M>>>>() {
+        observers.push(observer);
+    }
+}
+
+pub(crate) fn observers<M: Model>() -> Vec<Arc<dyn Observer<M>>> {
+    return OBSERVERS.read().unwrap()
+        .get(&TypeId::of::<M>())
+        .and_then(|observers| observers.downcast_ref::<Vec<Arc<dyn Observer<M>>>>())
+        .cloned()
+        .unwrap_or_default();
+}
+
+/// A transaction for the hooks of `M` to abort the operation in, `None` when nothing observes `M`.
+///
+/// In a transaction of the caller it is a savepoint, so an aborted operation is undone without rolling back the caller's.
+pub(crate) async fn begin<E: Executor, M: Model>(db: &Database<E>) -> Result<Option<Transaction<E>>> {
+    if observers::<M>().is_empty() {
+        return Ok(None);
+    }
+
+    return Ok(Some(db.transaction().await?));
+}
+
+/// Commits the transaction of `begin` when the operation succeeded, otherwise rolls it back and returns the error.
+pub(crate) async fn finish<E: Executor, T>(transaction: Option<Transaction<E>>, result: Result<T>) -> Result<T> {
+    let Some(transaction) = transaction else {
+        return result;
+    };
+
+    return match result {
+        Ok(value) => transaction.commit().await.map(|_| value),
+        Err(error) => {
+            transaction.rollback().await?;
+
+            Err(error)
+        },
+    };
+}
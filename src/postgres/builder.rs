@@ -0,0 +1,285 @@
+use std::vec;
+
+use anyhow::{Ok, Result};
+
+use sqlx::Postgres;
+
+use crate::{Driver, query::{QueryBuilder, QueryStatement, WhereQuery}};
+
+#[derive(Debug)]
+pub(crate) struct Builder<'q> {
+    statement: &'q QueryStatement,
+}
+
+impl <'q>QueryBuilder<'q> for Builder<'q> {
+    fn new(statement: &'q QueryStatement) -> Self {
+        return Self {
+            statement: statement
+        };
+    }
+
+    fn insert(&self) -> Result<String> {
+        let columns = self.statement.columns.clone().unwrap();
+
+        let upsert = match &self.statement.upsert {
+            Some((unique_by, update)) if update.len() == 0 => format!(" ON CONFLICT ({}) DO NOTHING", unique_by.join(", ")),
+            Some((unique_by, update)) => format!(
+                " ON CONFLICT ({}) DO UPDATE SET {}",
+                unique_by.join(", "),
+                update.iter().map(|column| format!("{} = excluded.{}", column, column)).collect::<Vec<_>>().join(", ")
+            ),
+            None => String::new(),
+        };
+
+        return Ok(format!(
+            "INSERT INTO {} ({}) VALUES ({}){}",
+            self.statement.table,
+            columns.join(", "),
+            std::iter::repeat_n("?", columns.len()).collect::<Vec<_>>().join(", "),
+            upsert
+        ));
+    }
+    
+    fn update(&self) -> Result<String> {
+        let mut sql = vec![
+            format!("UPDATE {}", self.statement.table),
+            format!("SET {}", self.statement
+                .columns
+                .clone()
+                .unwrap()
+                .iter()
+                .map(|f| format!("{} = ?", f))
+                .chain(self.statement.raw_columns.iter().map(|(column, expression)| format!("{} = {}", column, expression)))
+                .collect::<Vec<_>>()
+                .join(", ")
+            )
+        ];
+
+        if self.statement.where_queries.len() != 0 {
+            sql.extend([
+                "WHERE".to_string(),
+                    self.r#where().unwrap(),
+            ]);
+        }
+
+        return Ok(sql.join(" "));
+    }
+    
+    fn delete(&self) -> Result<String> {
+        let mut sql = vec![format!("DELETE FROM {}", self.statement.table)];
+
+        if self.statement.where_queries.len() != 0 {
+            sql.extend([
+                "WHERE".to_string(),
+                    self.r#where().unwrap(),
+            ]);
+        }
+
+        return Ok(sql.join(" "));
+    }
+
+    fn query(&self) -> Result<String> {
+        let mut sql = vec![
+            "SELECT".to_string(),
+                self.select().unwrap(),
+            "FROM".to_string(),
+                self.statement.table.clone(),
+        ];
+
+        if self.statement.join.len() != 0 {
+            sql.push(self.join().unwrap());
+        }
+
+        if self.statement.where_queries.len() != 0 {
+            sql.extend([
+                "WHERE".to_string(),
+                    self.r#where().unwrap(),
+            ]);
+        }
+
+        if self.statement.order_by.len() != 0 {
+            sql.extend([
+                "ORDER BY".to_string(),
+                    self.order_by().unwrap(),
+            ]);
+        }
+
+        if let Some(limit) = self.statement.limit {
+            sql.push(format!("LIMIT {}", limit));
+
+            if let Some(page) = self.statement.page.filter(|page| *page > 1) {
+                sql.push(format!("OFFSET {}", (page - 1) * limit));
+            }
+        }
+
+        if let Some(lock) = self.statement.lock.as_ref().and_then(Postgres::lock) {
+            sql.push(lock);
+        }
+
+        return Ok(sql.join(" "));
+    }
+
+    fn select(&self) -> Result<String> {
+        if self.statement.select.len() == 0 {
+            return Ok(String::from("*"));
+        }
+
+        return Ok(self.statement.select.join(", "));
+    }
+
+    fn join(&self) -> Result<String> {
+        let mut conditions: Vec<String> = Vec::new();
+
+        for join in &self.statement.join {
+            match join.join_type {
+                crate::query::JoinType::LeftJoin => conditions.push(format!("LEFT JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::RightJoin => conditions.push(format!("RIGHT JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::InnerJoin => conditions.push(format!("INNER JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::FullOuterJoin => conditions.push(format!("FULL OUTER JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+                crate::query::JoinType::CrossJoin => conditions.push(format!("CROSS JOIN {} ON {} {} {}", join.table, join.column, join.operator, join.column_table)),
+            }
+        }
+
+        return Ok(conditions.join(" "));
+    }
+
+    fn r#where(&self) -> Result<String> {
+        return self.conditions(&self.statement.where_queries);
+    }
+
+    fn order_by(&self) -> Result<String> {
+        return Ok(self.statement.order_by
+            .iter()
+            .map(|order| match order.order {
+                crate::query::Order::ASC => format!("{} ASC", order.column),
+                crate::query::Order::DESC => format!("{} DESC", order.column),
+            })
+            .collect::<Vec<_>>()
+            .join(", "));
+    }
+    
+    fn group_by(&self) -> Result<String> {
+        todo!()
+    }
+    
+}
+
+impl <'q>Builder<'q> {
+    fn conditions(&self, where_queries: &[WhereQuery]) -> Result<String> {
+        let mut conditions: Vec<String> = Vec::new();
+
+        for where_query in where_queries {
+            if let Some(position) = &where_query.position {
+                match position {
+                    crate::query::QueryPosition::AND => conditions.push(String::from("AND")),
+                    crate::query::QueryPosition::OR => conditions.push(String::from("OR")),
+                }
+            }
+
+            match &where_query.group {
+                Some(group) => conditions.push(format!("({})", self.conditions(group).unwrap())),
+                None => {
+                    match where_query.operator.clone().unwrap().as_str().to_lowercase().as_str() {
+                        "like" => conditions.push(format!("{} LIKE '%' || ? || '%'", where_query.column.clone().unwrap())),
+                        "is null" => conditions.push(format!("{} IS NULL", where_query.column.clone().unwrap())),
+                        "is not null" => conditions.push(format!("{} IS NOT NULL", where_query.column.clone().unwrap())),
+                        "raw" => conditions.push(where_query.column.clone().unwrap()),
+                        "in" => conditions.push(match where_query.values.unwrap_or(0) {
+                            0 => String::from("1 = 0"),
+                            values => format!("{} IN ({})", where_query.column.clone().unwrap(), vec!["?"; values].join(", ")),
+                        }),
+                        _ => conditions.push(format!("{} {} ?", where_query.column.clone().unwrap(), where_query.operator.clone().unwrap())),
+                    }
+                },
+            }
+        }
+
+        return Ok(conditions.join(" "));
+    }
+}
+
+/// Numbers the `?` placeholders of the builders as `$1`, `$2`, ... in the order they appear.
+///
+/// Placeholders already numbered, like those of a subquery built on its own, are renumbered so the query can embed it.
+/// Quoted strings and identifiers are left untouched.
+pub(crate) fn numbered(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut numbered = String::with_capacity(sql.len());
+    let mut count = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        match c {
+            '\'' | '"' => {
+                let end = chars[i + 1..].iter().position(|&e| e == c).map(|p| i + 1 + p).unwrap_or(chars.len() - 1);
+
+                numbered.extend(&chars[i..=end]);
+                i = end + 1;
+            },
+            '?' => {
+                count += 1;
+                numbered.push_str(&format!("${}", count));
+                i += 1;
+            },
+            '$' if chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()) => {
+                let end = chars[i + 1..].iter().position(|e| !e.is_ascii_digit()).map(|p| i + 1 + p).unwrap_or(chars.len());
+
+                count += 1;
+                numbered.push_str(&format!("${}", count));
+                i = end;
+            },
+            _ => {
+                numbered.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    return numbered;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::{Lock, LockMode, LockWait, QueryBuilder, QueryStatement};
+
+    use super::{Builder, numbered};
+
+    #[test]
+    fn numbers_placeholders_in_order() {
+        let mut statement = QueryStatement::new("users");
+
+        statement.and_where("name", "like");
+        statement.and_where_raw("EXISTS (SELECT 1 FROM posts WHERE posts.user_id = users.id AND title = $1)");
+        statement.and_where("id", ">");
+
+        assert_eq!(
+            numbered(&Builder::new(&statement).query().unwrap()),
+            "SELECT * FROM users WHERE name LIKE '%' || $1 || '%' AND EXISTS (SELECT 1 FROM posts WHERE posts.user_id = users.id AND title = $2) AND id > $3"
+        );
+    }
+
+    #[test]
+    fn ends_selects_with_the_lock() {
+        let locked = |mode, wait| {
+            let mut statement = QueryStatement::new("jobs");
+
+            statement.and_where("status", "=");
+            statement.limit = Some(1);
+            statement.lock = Some(Lock { mode: mode, wait: wait });
+
+            return numbered(&Builder::new(&statement).query().unwrap());
+        };
+
+        assert_eq!(locked(LockMode::Update, LockWait::Wait), "SELECT * FROM jobs WHERE status = $1 LIMIT 1 FOR UPDATE");
+        assert_eq!(locked(LockMode::Update, LockWait::SkipLocked), "SELECT * FROM jobs WHERE status = $1 LIMIT 1 FOR UPDATE SKIP LOCKED");
+        assert_eq!(locked(LockMode::Share, LockWait::Wait), "SELECT * FROM jobs WHERE status = $1 LIMIT 1 FOR SHARE");
+        assert_eq!(locked(LockMode::Update, LockWait::NoWait), "SELECT * FROM jobs WHERE status = $1 LIMIT 1 FOR UPDATE NOWAIT");
+    }
+
+    #[test]
+    fn leaves_quoted_question_marks() {
+        assert_eq!(numbered("SELECT '?', \"a?\" FROM t WHERE a = ? AND b = ?"), "SELECT '?', \"a?\" FROM t WHERE a = $1 AND b = $2");
+    }
+}
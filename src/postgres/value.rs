@@ -0,0 +1,165 @@
+use anyhow::Result;
+use sqlx::{Decode, Encode, FromRow, Postgres, Row, TypeInfo, ValueRef, encode::IsNull, error::BoxDynError, postgres::{PgArgumentBuffer, PgRow, PgTypeInfo, PgValueRef, types::Oid}, types::Type};
+
+use crate::{Decodable, Driver, Value, query::{Lock, LockMode, LockWait}};
+
+impl Type<Postgres> for Value {
+    fn type_info() -> PgTypeInfo {
+        return <str as Type<Postgres>>::type_info();
+    }
+
+    fn compatible(_: &PgTypeInfo) -> bool {
+        return true;
+    }
+}
+
+/// `Value::Null` is sent untyped (oid 0) so the server infers the column type.
+impl<'q> Encode<'q, Postgres> for Value {
+    fn encode_by_ref(&self, buf: &mut <Postgres as sqlx::Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        return match self {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Bool(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Int(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Float(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Text(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Bytes(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Json(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Uuid(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::DateTime(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Date(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+            Value::Time(value) => Encode::<Postgres>::encode_by_ref(value, buf),
+        };
+    }
+
+    fn produces(&self) -> Option<PgTypeInfo> {
+        return Some(match self {
+            Value::Null => PgTypeInfo::with_oid(Oid(0)),
+            Value::Bool(_) => <bool as Type<Postgres>>::type_info(),
+            Value::Int(_) => <i64 as Type<Postgres>>::type_info(),
+            Value::Float(_) => <f64 as Type<Postgres>>::type_info(),
+            Value::Text(_) => <str as Type<Postgres>>::type_info(),
+            Value::Bytes(_) => <Vec<u8> as Type<Postgres>>::type_info(),
+            Value::Json(_) => <serde_json::Value as Type<Postgres>>::type_info(),
+            Value::Uuid(_) => <uuid::Uuid as Type<Postgres>>::type_info(),
+            Value::DateTime(_) => <chrono::DateTime<chrono::Utc> as Type<Postgres>>::type_info(),
+            Value::Date(_) => <chrono::NaiveDate as Type<Postgres>>::type_info(),
+            Value::Time(_) => <chrono::NaiveTime as Type<Postgres>>::type_info(),
+        });
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Value {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        if value.is_null() {
+            return Ok(Value::Null);
+        }
+
+        let name = value.type_info().name().to_string();
+
+        return Ok(match name.as_str() {
+            "BOOL" => Value::Bool(<bool as Decode<Postgres>>::decode(value)?),
+            "INT2" => Value::Int(<i16 as Decode<Postgres>>::decode(value)?.into()),
+            "INT4" => Value::Int(<i32 as Decode<Postgres>>::decode(value)?.into()),
+            "INT8" => Value::Int(<i64 as Decode<Postgres>>::decode(value)?),
+            "FLOAT4" => Value::Float(<f32 as Decode<Postgres>>::decode(value)?.into()),
+            "FLOAT8" => Value::Float(<f64 as Decode<Postgres>>::decode(value)?),
+            "BYTEA" => Value::Bytes(<Vec<u8> as Decode<Postgres>>::decode(value)?),
+            "JSON" | "JSONB" => Value::Json(<serde_json::Value as Decode<Postgres>>::decode(value)?),
+            "UUID" => Value::Uuid(<uuid::Uuid as Decode<Postgres>>::decode(value)?),
+            "TIMESTAMPTZ" => Value::DateTime(<chrono::DateTime<chrono::Utc> as Decode<Postgres>>::decode(value)?),
+            "TIMESTAMP" => Value::DateTime(<chrono::NaiveDateTime as Decode<Postgres>>::decode(value)?.and_utc()),
+            "DATE" => Value::Date(<chrono::NaiveDate as Decode<Postgres>>::decode(value)?),
+            "TIME" => Value::Time(<chrono::NaiveTime as Decode<Postgres>>::decode(value)?),
+            _ => Value::Text(<String as Decode<Postgres>>::decode(value)?),
+        });
+    }
+}
+
+/// A bound value, its bytes and type. Arrays and records of custom types, which get their type oids
+/// patched in when the statement is prepared, can't be bound this way.
+#[derive(Clone)]
+pub struct Encoded {
+    bytes: Option<Vec<u8>>,
+    type_info: PgTypeInfo,
+}
+
+impl Type<Postgres> for Encoded {
+    fn type_info() -> PgTypeInfo {
+        return <str as Type<Postgres>>::type_info();
+    }
+
+    fn compatible(_: &PgTypeInfo) -> bool {
+        return true;
+    }
+}
+
+impl<'q> Encode<'q, Postgres> for Encoded {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        let Some(bytes) = &self.bytes else {
+            return Ok(IsNull::Yes);
+        };
+
+        buf.extend_from_slice(bytes);
+
+        return Ok(IsNull::No);
+    }
+
+    fn produces(&self) -> Option<PgTypeInfo> {
+        return Some(self.type_info.clone());
+    }
+}
+
+impl Driver for Postgres {
+    type Argument = Value;
+    type Encoded<'q> = Encoded;
+
+    const RETURNING: bool = true;
+
+    fn encode<'q, T: 'q + Encode<'q, Self> + Type<Self>>(value: T) -> Result<Encoded> {
+        let type_info = value.produces().unwrap_or_else(T::type_info);
+        let mut buf = PgArgumentBuffer::default();
+
+        let bytes = match value.encode(&mut buf).map_err(|e| anyhow::anyhow!(e))? {
+            IsNull::Yes => None,
+            IsNull::No => Some(buf.to_vec()),
+        };
+
+        return Ok(Encoded { bytes: bytes, type_info: type_info });
+    }
+
+    /// Text is encoded as its bytes, so the buffer of a string argument is the string.
+    fn text<'q, T: Encode<'q, Self> + Type<Self>>(value: &T) -> Option<String> {
+        if !<str as Type<Postgres>>::compatible(&value.produces().unwrap_or_else(T::type_info)) {
+            return None;
+        }
+
+        let mut buf = PgArgumentBuffer::default();
+
+        if let IsNull::Yes = value.encode_by_ref(&mut buf).ok()? {
+            return None;
+        }
+
+        return String::from_utf8(buf.to_vec()).ok();
+    }
+
+    fn get(row: &PgRow, column: &str) -> Result<Value> {
+        return Ok(row.try_get::<Value, _>(column)?);
+    }
+
+    fn lock(lock: &Lock) -> Option<String> {
+        let mode = match lock.mode {
+            LockMode::Update => "FOR UPDATE",
+            LockMode::Share => "FOR SHARE",
+        };
+
+        return Some(match lock.wait {
+            LockWait::Wait => mode.to_string(),
+            LockWait::SkipLocked => format!("{} SKIP LOCKED", mode),
+            LockWait::NoWait => format!("{} NOWAIT", mode),
+        });
+    }
+
+    fn decode<M: Decodable>(row: &PgRow) -> Result<M, sqlx::Error> {
+        return <M as FromRow<PgRow>>::from_row(row);
+    }
+}
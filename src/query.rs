@@ -1,8 +1,12 @@
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData, ops::{Deref, DerefMut}, sync::Arc};
 
-use anyhow::Result;
-use serde::Serialize;
-use sqlx::{Encode, Transaction as SqlxTransaction, types::Type};
+use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sqlx::{Arguments as _, Column, Encode, Row, Transaction as SqlxTransaction, pool::PoolConnection, types::Type};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{Database, Decodable, Driver, Executor, Value};
 
 pub(crate) trait QueryBuilder<'q> {
     fn new(statement: &'q QueryStatement) -> Self where Self: Sized;
@@ -13,6 +17,8 @@ pub(crate) trait QueryBuilder<'q> {
     fn select(&self) -> Result<String>;
     fn join(&self) -> Result<String>;
     fn r#where(&self) -> Result<String>;
+    fn order_by(&self) -> Result<String>;
+    #[allow(dead_code)]
     fn group_by(&self) -> Result<String>;
 }
 
@@ -21,7 +27,9 @@ pub struct WhereQuery {
     pub column: Option<String>,
     pub operator: Option<String>,
     pub position: Option<QueryPosition>,
-    pub group: Option<Box<WhereQuery>>
+    pub group: Option<Vec<WhereQuery>>,
+    /// Number of values bound for an `IN` condition.
+    pub values: Option<usize>
 }
 
 #[derive(Debug)]
@@ -31,6 +39,15 @@ pub struct WhereQueryGroup<'q, DB: sqlx::Database> {
     _life: PhantomData<&'q ()>
 }
 
+impl <'q, DB>Default for WhereQueryGroup<'q, DB>
+where
+    DB: sqlx::Database
+{
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
 impl <'q, DB>WhereQueryGroup<'q, DB>
 where
     DB: sqlx::Database
@@ -43,7 +60,7 @@ where
         }
     }
 
-    pub fn r#where<T: 'q + Encode<'q, DB> + Type<DB>>(&mut self, column: &str, operator: &str, val: T) -> &mut Self {
+    pub fn r#where<T: 'q + Encode<'q, DB> + Type<DB>>(&mut self, _column: &str, _operator: &str, _val: T) -> &mut Self {
         todo!()
     }
 }
@@ -56,12 +73,47 @@ pub enum QueryPosition {
     OR
 }
 
+/// Which rows of a soft deleting model a query sees.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Trashed {
+    #[default]
+    Without,
+    With,
+    Only
+}
+
 #[derive(Clone, Debug)]
 pub enum Order {
     ASC,
     DESC
 }
 
+/// A row lock taken by a select, held until the transaction it runs in ends.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lock {
+    pub mode: LockMode,
+    pub wait: LockWait,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockMode {
+    /// `FOR UPDATE`, other transactions can't lock or change the rows.
+    Update,
+    /// `FOR SHARE`, other transactions can read-lock the rows but not change them.
+    Share,
+}
+
+/// What a select does when a row is already locked by another transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum LockWait {
+    #[default]
+    Wait,
+    /// `SKIP LOCKED`, leaves the locked rows out, like jobs already claimed by another worker.
+    SkipLocked,
+    /// `NOWAIT`, fails right away.
+    NoWait,
+}
+
 #[derive(Clone, Debug)]
 pub struct OrderQuery {
     pub column: String,
@@ -88,9 +140,105 @@ pub struct JoinQuery {
 }
 
 #[derive(Clone, Debug)]
-pub struct Statement<'q, DB: sqlx::Database> {
+pub struct Statement<'q, DB: Driver> {
     pub query: QueryStatement,
-    pub arguments: DB::Arguments<'q>, 
+    pub arguments: Arguments<'q, DB>,
+}
+
+/// The values bound to a statement, encoded as they are bound and handed to sqlx when it runs.
+///
+/// Values bound in the select, like those of `with_count_where`, are kept apart and passed first, since
+/// the select is rendered before the other clauses whatever order the builder methods were called in.
+/// A value that failed to bind is kept as the error the statement returns when it runs.
+pub struct Arguments<'q, DB: Driver> {
+    select: Vec<DB::Encoded<'q>>,
+    values: Vec<DB::Encoded<'q>>,
+    error: Option<Arc<anyhow::Error>>,
+}
+
+impl <'q, DB: Driver>Default for Arguments<'q, DB> {
+    fn default() -> Self {
+        return Self {
+            select: Vec::new(),
+            values: Vec::new(),
+            error: None,
+        };
+    }
+}
+
+impl <'q, DB: Driver>Clone for Arguments<'q, DB> {
+    fn clone(&self) -> Self {
+        return Self {
+            select: self.select.clone(),
+            values: self.values.clone(),
+            error: self.error.clone(),
+        };
+    }
+}
+
+impl <'q, DB: Driver>std::fmt::Debug for Arguments<'q, DB> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f.debug_struct("Arguments")
+            .field("select", &self.select.len())
+            .field("values", &self.values.len())
+            .field("error", &self.error)
+            .finish();
+    }
+}
+
+impl <'q, DB: Driver>Arguments<'q, DB> {
+    pub fn add<T: 'q + Encode<'q, DB> + Type<DB>>(&mut self, value: T) -> Result<()> {
+        self.values.push(DB::encode(value)?);
+
+        return Ok(());
+    }
+
+    /// Same as `add` for the builders returning `&mut Self`, an error is kept for `build` to return.
+    pub(crate) fn push<T: 'q + Encode<'q, DB> + Type<DB>>(&mut self, value: T) {
+        if let Err(error) = self.add(value) {
+            self.fail(error);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.select.len() + self.values.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    /// Keeps the first error, returned by `build` instead of the arguments.
+    pub(crate) fn fail(&mut self, error: anyhow::Error) {
+        self.error.get_or_insert(Arc::new(error));
+    }
+
+    /// Adds the values of a subquery rendered in the select.
+    pub(crate) fn select(&mut self, other: Self) {
+        self.select.extend(other.select.into_iter().chain(other.values));
+        self.error = self.error.take().or(other.error);
+    }
+
+    /// Adds the values of a subquery rendered after the select, in a where clause.
+    pub(crate) fn extend(&mut self, other: Self) {
+        self.values.extend(other.select.into_iter().chain(other.values));
+        self.error = self.error.take().or(other.error);
+    }
+
+    /// The sqlx arguments of the statement, select values first, or the error a value failed to bind with.
+    pub fn build(&self) -> Result<DB::Arguments<'q>> {
+        if let Some(error) = &self.error {
+            return Err(anyhow!("{:#}", error));
+        }
+
+        let mut arguments: DB::Arguments<'q> = Default::default();
+
+        for value in self.select.iter().chain(&self.values) {
+            arguments.add(value.clone()).map_err(|e| anyhow!(e))?;
+        }
+
+        return Ok(arguments);
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -113,6 +261,15 @@ pub struct QueryStatement {
     pub limit: Option<u64>,
     pub page: Option<u64>, // TODO: must use `offset` or `page` must decide...
     pub columns: Option<Vec<String>>,
+    /// Columns `update` sets to a SQL expression instead of a bound value.
+    pub raw_columns: Vec<(String, String)>,
+    /// The primary key of the inserted row when known before the insert, `insert_as` reads the row back by it instead of `rowid`.
+    pub key: Option<(String, Value)>,
+    /// The primary key of the model the query was started from, MySQL's `insert_as` reads the row back by it, or by `id`.
+    pub primary_key: Option<String>,
+    pub lock: Option<Lock>,
+    /// The unique columns of `upsert_with` and the columns it updates when a row with the same values exists.
+    pub upsert: Option<(Vec<String>, Vec<String>)>,
 }
 
 impl QueryStatement {
@@ -128,7 +285,262 @@ impl QueryStatement {
             limit: None,
             page: None,
             columns: None,
+            raw_columns: Vec::new(),
+            key: None,
+            primary_key: None,
+            lock: None,
+            upsert: None,
+        }
+    }
+
+    /// Nests the current where clauses in parentheses so an appended condition applies to all of them.
+    pub(crate) fn group_where(&mut self) {
+        if self.where_queries.len() < 2 {
+            return;
         }
+
+        let queries = std::mem::take(&mut self.where_queries);
+
+        self.where_queries.push(WhereQuery {
+            column: None,
+            operator: None,
+            position: None,
+            group: Some(queries),
+            values: None
+        });
+    }
+
+    /// Appends an `AND` condition on a SQL expression, the caller binds the values of its placeholders.
+    pub(crate) fn and_where_raw(&mut self, sql: &str) {
+        self.and_where(sql, "raw");
+    }
+
+    /// Appends an `AND` condition, the caller binds its value.
+    pub(crate) fn and_where(&mut self, column: &str, operator: &str) {
+        self.where_queries.push(WhereQuery {
+            column: Some(column.to_string()),
+            operator: Some(operator.to_string()),
+            position: if self.where_queries.is_empty() { None } else { Some(QueryPosition::AND) },
+            group: None,
+            values: None
+        });
+    }
+}
+
+/// Rewrites `:name` placeholders into the driver's positional form.
+///
+/// Every occurrence gets its own positional argument, so a name may be repeated. Quoted strings,
+/// identifiers, comments, postgres `::` casts and mysql `@variables` are left untouched.
+pub(crate) fn named_parameters<DB: Driver>(sql: &str, params: Vec<(String, Value)>) -> Result<(String, Vec<Value>)> {
+    let params: HashMap<String, Value> = params.into_iter().collect();
+    let chars: Vec<char> = sql.chars().collect();
+    let mut arguments: DB::Arguments<'_> = Default::default();
+    let mut values: Vec<Value> = Vec::new();
+    let mut rewritten = String::with_capacity(sql.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            '\'' | '"' | '`' => {
+                let end = chars[i + 1..].iter().position(|&e| e == c).map(|p| i + 1 + p).unwrap_or(chars.len() - 1);
+
+                rewritten.extend(&chars[i..=end]);
+                i = end + 1;
+            },
+            '-' if next == Some('-') => {
+                let end = chars[i..].iter().position(|&e| e == '\n').map(|p| i + p).unwrap_or(chars.len() - 1);
+
+                rewritten.extend(&chars[i..=end]);
+                i = end + 1;
+            },
+            '/' if next == Some('*') => {
+                let end = (i + 2..chars.len()).find(|&e| chars[e - 1] == '*' && chars[e] == '/').unwrap_or(chars.len() - 1);
+
+                rewritten.extend(&chars[i..=end]);
+                i = end + 1;
+            },
+            ':' if next == Some(':') => {
+                rewritten.extend([c, c]);
+                i += 2;
+            },
+            ':' if next.is_some_and(|n| n.is_ascii_alphabetic() || n == '_') => {
+                let end = chars[i + 1..].iter().position(|&e| !(e.is_ascii_alphanumeric() || e == '_')).map(|p| i + 1 + p).unwrap_or(chars.len());
+                let name: String = chars[i + 1..end].iter().collect();
+                let value = params.get(&name).ok_or_else(|| anyhow!("missing value for named parameter `{}`", name))?;
+
+                arguments.add(DB::Argument::from(value.clone())).map_err(|e| anyhow!(e))?;
+                arguments.format_placeholder(&mut rewritten)?;
+                values.push(value.clone());
+                i = end;
+            },
+            _ => {
+                rewritten.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    return Ok((rewritten, values));
+}
+
+/// Prefix of the aliases key columns are selected as, so they can be read next to the caller's row type.
+pub(crate) const KEY_PREFIX: &str = "flyer_key_";
+
+pub(crate) fn key_alias(index: usize) -> String {
+    return format!("{}{}", KEY_PREFIX, index);
+}
+
+/// A row decoded as `O` together with the values of its `key_alias` columns.
+pub(crate) struct Keyed<DB, O> {
+    pub keys: Vec<Value>,
+    pub row: O,
+    _marker: PhantomData<fn() -> DB>
+}
+
+impl <'r, DB, O>sqlx::FromRow<'r, DB::Row> for Keyed<DB, O>
+where
+    DB: Driver,
+    O: sqlx::FromRow<'r, DB::Row>
+{
+    fn from_row(row: &'r DB::Row) -> Result<Self, sqlx::Error> {
+        let mut keys = Vec::new();
+
+        for column in row.columns().iter().filter(|column| column.name().starts_with(KEY_PREFIX)) {
+            keys.push(DB::get(row, column.name()).map_err(|e| sqlx::Error::ColumnDecode { index: column.name().to_string(), source: e.into() })?);
+        }
+
+        return Ok(Self {
+            keys: keys,
+            row: O::from_row(row)?,
+            _marker: PhantomData
+        });
+    }
+}
+
+/// A row decoded with `Driver::decode`.
+pub(crate) struct Decoded<DB, M> {
+    pub row: M,
+    _marker: PhantomData<fn() -> DB>
+}
+
+impl <'r, DB, M>sqlx::FromRow<'r, DB::Row> for Decoded<DB, M>
+where
+    DB: Driver,
+    M: Decodable
+{
+    fn from_row(row: &'r DB::Row) -> Result<Self, sqlx::Error> {
+        return Ok(Self {
+            row: DB::decode(row)?,
+            _marker: PhantomData
+        });
+    }
+}
+
+/// A row read column by column as `Value`s, without its `key_alias` columns.
+pub(crate) struct Values<DB> {
+    pub values: Vec<(String, Value)>,
+    _marker: PhantomData<fn() -> DB>
+}
+
+impl <'r, DB>sqlx::FromRow<'r, DB::Row> for Values<DB>
+where
+    DB: Driver
+{
+    fn from_row(row: &'r DB::Row) -> Result<Self, sqlx::Error> {
+        let mut values = Vec::new();
+
+        for column in row.columns().iter().filter(|column| !column.name().starts_with(KEY_PREFIX)) {
+            values.push((column.name().to_string(), DB::get(row, column.name()).map_err(|e| sqlx::Error::ColumnDecode { index: column.name().to_string(), source: e.into() })?));
+        }
+
+        return Ok(Self {
+            values: values,
+            _marker: PhantomData
+        });
+    }
+}
+
+/// Position in a keyset ordered result, handed out base64 encoded as `next_cursor`/`prev_cursor`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct Cursor {
+    pub forward: bool,
+    values: Vec<CursorValue>
+}
+
+impl Cursor {
+    pub fn encode(forward: bool, values: &[Value]) -> String {
+        let cursor = Self {
+            forward: forward,
+            values: values.iter().cloned().map(CursorValue::from).collect()
+        };
+
+        return URL_SAFE_NO_PAD.encode(serde_json::to_vec(&cursor).unwrap());
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        return Ok(serde_json::from_slice(&URL_SAFE_NO_PAD.decode(token)?)?);
+    }
+
+    pub fn values(&self) -> Result<Vec<Value>> {
+        return self.values.iter().cloned().map(Value::try_from).collect();
+    }
+}
+
+/// A cursor value tagged with its `Value` variant, so a `Uuid` or `DateTime` key is compared as one on the next page.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+enum CursorValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(String),
+    Json(serde_json::Value),
+    Uuid(uuid::Uuid),
+    DateTime(chrono::DateTime<chrono::Utc>),
+    Date(chrono::NaiveDate),
+    Time(chrono::NaiveTime),
+}
+
+impl From<Value> for CursorValue {
+    fn from(value: Value) -> Self {
+        return match value {
+            Value::Null => CursorValue::Null,
+            Value::Bool(value) => CursorValue::Bool(value),
+            Value::Int(value) => CursorValue::Int(value),
+            Value::Float(value) => CursorValue::Float(value),
+            Value::Text(value) => CursorValue::Text(value),
+            Value::Bytes(value) => CursorValue::Bytes(URL_SAFE_NO_PAD.encode(value)),
+            Value::Json(value) => CursorValue::Json(value),
+            Value::Uuid(value) => CursorValue::Uuid(value),
+            Value::DateTime(value) => CursorValue::DateTime(value),
+            Value::Date(value) => CursorValue::Date(value),
+            Value::Time(value) => CursorValue::Time(value),
+        };
+    }
+}
+
+impl TryFrom<CursorValue> for Value {
+    type Error = anyhow::Error;
+
+    fn try_from(value: CursorValue) -> Result<Self> {
+        return Ok(match value {
+            CursorValue::Null => Value::Null,
+            CursorValue::Bool(value) => Value::Bool(value),
+            CursorValue::Int(value) => Value::Int(value),
+            CursorValue::Float(value) => Value::Float(value),
+            CursorValue::Text(value) => Value::Text(value),
+            CursorValue::Bytes(value) => Value::Bytes(URL_SAFE_NO_PAD.decode(value)?),
+            CursorValue::Json(value) => Value::Json(value),
+            CursorValue::Uuid(value) => Value::Uuid(value),
+            CursorValue::DateTime(value) => Value::DateTime(value),
+            CursorValue::Date(value) => Value::Date(value),
+            CursorValue::Time(value) => Value::Time(value),
+        });
     }
 }
 
@@ -139,7 +551,7 @@ pub(crate) struct Total {
 
 impl <'q, DB>Statement<'q, DB>
 where
-    DB: sqlx::Database
+    DB: Driver
 {
     pub(crate) fn new(table: &str) -> Self {
         return Self {
@@ -147,26 +559,87 @@ where
             arguments: Default::default(),
         }
     }
+
+    /// `clone` for generic code, where the driver isn't known to be `Clone`.
+    pub(crate) fn duplicate(&self) -> Self {
+        return Self {
+            query: self.query.clone(),
+            arguments: self.arguments.clone(),
+        }
+    }
 }
 
-#[derive(Debug)]
-pub struct Transaction<'t, T: sqlx::Database> {
-    transaction: SqlxTransaction<'t, T>
+/// The open transaction an executor's queries run in, shared by the copies made with `Executor::begin`.
+pub(crate) type SharedTransaction<DB> = Arc<Mutex<Option<SqlxTransaction<'static, DB>>>>;
+
+/// A connection from the pool, or the executor's transaction.
+pub(crate) enum Connection<'c, DB: sqlx::Database> {
+    Pool(PoolConnection<DB>),
+    Transaction(MutexGuard<'c, Option<SqlxTransaction<'static, DB>>>)
 }
 
-impl <'t, T: sqlx::Database>Transaction<'t, T> {
-    pub(crate) fn new(transaction: SqlxTransaction<'t, T>) -> Self {
+impl <'c, DB: sqlx::Database>Connection<'c, DB> {
+    pub(crate) async fn acquire(pool: &sqlx::Pool<DB>, transaction: &'c Option<SharedTransaction<DB>>) -> Result<Self> {
+        let Some(transaction) = transaction else {
+            return Ok(Connection::Pool(pool.acquire().await?));
+        };
+
+        let transaction = transaction.lock().await;
+
+        if transaction.is_none() {
+            return Err(anyhow!("the transaction was already committed or rolled back"));
+        }
+
+        return Ok(Connection::Transaction(transaction));
+    }
+}
+
+impl <'c, DB: sqlx::Database>Deref for Connection<'c, DB> {
+    type Target = DB::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        return match self {
+            Connection::Pool(connection) => connection,
+            Connection::Transaction(transaction) => transaction.as_ref().unwrap(),
+        };
+    }
+}
+
+impl <'c, DB: sqlx::Database>DerefMut for Connection<'c, DB> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return match self {
+            Connection::Pool(connection) => connection,
+            Connection::Transaction(transaction) => transaction.as_mut().unwrap(),
+        };
+    }
+}
+
+/// A `Database` whose queries run in a transaction, rolled back when dropped without `commit`.
+pub struct Transaction<E: Executor> {
+    database: Database<E>
+}
+
+impl <E: Executor>Transaction<E> {
+    pub(crate) fn new(database: Database<E>) -> Self {
         return Self {
-            transaction: transaction
+            database: database
         }
     }
 
     pub async fn commit(self) -> Result<()> {
-        return self.transaction.commit().await.map_err(|e| e.into());
+        return self.database.executor.commit().await;
     }
 
     pub async fn rollback(self) -> Result<()> {
-        return self.transaction.rollback().await.map_err(|e| e.into());
+        return self.database.executor.rollback().await;
+    }
+}
+
+impl <E: Executor>Deref for Transaction<E> {
+    type Target = Database<E>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.database;
     }
 }
 
@@ -175,5 +648,92 @@ pub struct Pagination<Entity> {
     pub total: u64,
     pub page: u64,
     pub per_page: u64,
+    pub last_page: u64,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub has_more: bool,
+    pub first: Option<String>,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    pub last: Option<String>,
     pub items: Vec<Entity>
 }
+
+impl <Entity>Pagination<Entity> {
+    pub fn new(items: Vec<Entity>, total: u64, page: u64, per_page: u64) -> Self {
+        let last_page = if per_page == 0 { 1 } else { total.div_ceil(per_page).max(1) };
+        let from = (page.max(1) - 1) * per_page + 1;
+
+        return Self {
+            total: total,
+            page: page,
+            per_page: per_page,
+            last_page: last_page,
+            from: if items.is_empty() { None } else { Some(from) },
+            to: if items.is_empty() { None } else { Some(from + items.len() as u64 - 1) },
+            has_more: page < last_page,
+            first: None,
+            prev: None,
+            next: None,
+            last: None,
+            items: items,
+        };
+    }
+
+    /// Fills `first`/`prev`/`next`/`last` from a base url and a query string template,
+    /// where `{page}` and `{per_page}` are replaced, e.g. `links("/users", "page={page}&limit={per_page}")`.
+    pub fn links(mut self, base_url: &str, template: &str) -> Self {
+        let url = |page: u64| {
+            let query = template
+                .replace("{page}", &page.to_string())
+                .replace("{per_page}", &self.per_page.to_string());
+
+            return format!("{}{}{}", base_url, if base_url.contains('?') { "&" } else { "?" }, query);
+        };
+
+        self.first = Some(url(1));
+        self.prev = if self.page > 1 { Some(url(self.page - 1)) } else { None };
+        self.next = if self.has_more { Some(url(self.page + 1)) } else { None };
+        self.last = Some(url(self.last_page));
+
+        return self;
+    }
+
+    /// Converts the items, e.g. from models into response DTOs, keeping the metadata.
+    pub fn map<T, F: FnMut(Entity) -> T>(self, callback: F) -> Pagination<T> {
+        return Pagination {
+            total: self.total,
+            page: self.page,
+            per_page: self.per_page,
+            last_page: self.last_page,
+            from: self.from,
+            to: self.to,
+            has_more: self.has_more,
+            first: self.first,
+            prev: self.prev,
+            next: self.next,
+            last: self.last,
+            items: self.items.into_iter().map(callback).collect(),
+        };
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CursorPagination<Entity> {
+    pub per_page: u64,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+    pub items: Vec<Entity>
+}
+
+impl <Entity>CursorPagination<Entity> {
+    /// Converts the items, keeping the cursors.
+    pub fn map<T, F: FnMut(Entity) -> T>(self, callback: F) -> CursorPagination<T> {
+        return CursorPagination {
+            per_page: self.per_page,
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+            items: self.items.into_iter().map(callback).collect(),
+        };
+    }
+}
@@ -0,0 +1,615 @@
+use std::{any::{Any, TypeId}, collections::HashMap, fmt, ops::{Deref, DerefMut}, sync::{Arc, LazyLock, RwLock}};
+
+use anyhow::{Result, anyhow};
+use futures::future::BoxFuture;
+use sqlx::FromRow;
+
+use crate::{Database, Decodable, Driver, Executor, Model, Query, Scope, Value, model, query::{self, Decoded, Keyed}};
+
+/// A relation declared on a model field, generated by `#[derive(Model)]` for `where_has` and `with_count`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    HasMany { table: &'static str, foreign_key: &'static str },
+    HasOne { table: &'static str, foreign_key: &'static str },
+    BelongsTo { table: &'static str, foreign_key: &'static str },
+    BelongsToMany { table: &'static str, pivot: &'static str, foreign_pivot_key: &'static str, related_pivot_key: &'static str },
+    /// The related rows hold `{name}_type` and `{name}_id` columns pointing at this model.
+    MorphMany { table: &'static str, name: &'static str },
+    MorphOne { table: &'static str, name: &'static str },
+    /// This model holds `{name}_type` and `{name}_id` columns pointing at a model of any type.
+    MorphTo { name: &'static str },
+}
+
+impl Relation {
+    /// The table of the related model, `None` for `MorphTo` which relates to any model.
+    pub fn table(&self) -> Option<&'static str> {
+        return match self {
+            Relation::HasMany { table, .. } => Some(table),
+            Relation::HasOne { table, .. } => Some(table),
+            Relation::BelongsTo { table, .. } => Some(table),
+            Relation::BelongsToMany { table, .. } => Some(table),
+            Relation::MorphMany { table, .. } => Some(table),
+            Relation::MorphOne { table, .. } => Some(table),
+            Relation::MorphTo { .. } => None,
+        };
+    }
+
+    /// Ties a query on the related model `R` to the current row of the outer query on `M`.
+    pub(crate) fn correlate<E: Executor, M: Model, R: Model>(&self, query: &mut Query<'_, E, R>) {
+        match self {
+            Relation::HasMany { foreign_key, .. } | Relation::HasOne { foreign_key, .. } => {
+                query.statement.query.and_where_raw(&format!("{}.{} = {}.{}", R::TABLE, foreign_key, M::TABLE, M::PRIMARY_KEY));
+            },
+            Relation::BelongsTo { foreign_key, .. } => {
+                query.statement.query.and_where_raw(&format!("{}.{} = {}.{}", R::TABLE, R::PRIMARY_KEY, M::TABLE, foreign_key));
+            },
+            Relation::BelongsToMany { pivot, foreign_pivot_key, related_pivot_key, .. } => {
+                query.join(pivot, &format!("{}.{}", pivot, related_pivot_key), &format!("{}.{}", R::TABLE, R::PRIMARY_KEY));
+                query.statement.query.and_where_raw(&format!("{}.{} = {}.{}", pivot, foreign_pivot_key, M::TABLE, M::PRIMARY_KEY));
+            },
+            Relation::MorphMany { name, .. } | Relation::MorphOne { name, .. } => {
+                query.statement.query.and_where_raw(&format!("{}.{}_id = {}.{}", R::TABLE, name, M::TABLE, M::PRIMARY_KEY));
+                query.and_where(&format!("{}.{}_type", R::TABLE, name), "=", <E::T as Driver>::Argument::from(Value::from(M::MORPH_NAME)));
+            },
+            Relation::MorphTo { name } => {
+                query.statement.query.and_where_raw(&format!("{}.{} = {}.{}_id", R::TABLE, R::PRIMARY_KEY, M::TABLE, name));
+                query.and_where(&format!("{}.{}_type", M::TABLE, name), "=", <E::T as Driver>::Argument::from(Value::from(R::MORPH_NAME)));
+            },
+        }
+    }
+}
+
+/// Loads the models of a `MORPH_NAME` by their keys, registered with `Model::register_morph`.
+type MorphLoader<E> = for<'a> fn(&'a E, Vec<Value>, &'a Eager) -> BoxFuture<'a, Result<Vec<Morphed>>>;
+
+/// Morph loaders by executor and `MORPH_NAME`, the values are `MorphLoader<E>`.
+static MORPH_MAP: LazyLock<RwLock<HashMap<(TypeId, &'static str), Box<dyn Any + Send + Sync>>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+pub(crate) fn register_morph<E: Executor, R: Model + Sync + Decodable>() {
+    let loader: MorphLoader<E> = load_morph::<E, R>;
+
+    MORPH_MAP.write().unwrap().insert((TypeId::of::<E>(), R::MORPH_NAME), Box::new(loader));
+}
+
+fn morph_loader<E: Executor>(name: &str) -> Result<MorphLoader<E>> {
+    return MORPH_MAP.read().unwrap()
+        .get(&(TypeId::of::<E>(), name))
+        .and_then(|loader| loader.downcast_ref::<MorphLoader<E>>().copied())
+        .ok_or_else(|| anyhow!("no model is registered for the morph type `{}`", name));
+}
+
+fn load_morph<'a, E: Executor, R: Model + Sync + Decodable>(db: &'a E, keys: Vec<Value>, eager: &'a Eager) -> BoxFuture<'a, Result<Vec<Morphed>>> {
+    return Box::pin(async move {
+        // A constraint is for one of the morphed types, the others are loaded without it.
+        let eager = Eager {
+            name: eager.name.clone(),
+            nested: eager.nested.clone(),
+            constraint: eager.constraint.clone().filter(|constraint| constraint.is::<Scope<E, R>>()),
+        };
+
+        let related = fetch::<E, R, _>(db, &eager, |query| {
+            query.where_in(&format!("{}.{}", R::TABLE, R::PRIMARY_KEY), keys);
+        }).await?;
+
+        return related.into_iter().map(|(_, related)| Morphed::new(related)).collect();
+    });
+}
+
+/// The model a `morph_to` relation points at, get it back with `downcast_ref`.
+#[derive(Clone)]
+pub struct Morphed {
+    pub name: &'static str,
+    key: Value,
+    model: Arc<dyn Any + Send + Sync>,
+}
+
+impl Morphed {
+    fn new<R: Model + Sync + Decodable>(model: R) -> Result<Self> {
+        return Ok(Self {
+            name: R::MORPH_NAME,
+            key: model.key()?,
+            model: Arc::new(model),
+        });
+    }
+
+    pub fn key(&self) -> &Value {
+        return &self.key;
+    }
+
+    pub fn is<R: Model>(&self) -> bool {
+        return self.model.is::<R>();
+    }
+
+    pub fn downcast_ref<R: Model>(&self) -> Option<&R> {
+        return self.model.downcast_ref::<R>();
+    }
+}
+
+impl fmt::Debug for Morphed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f.debug_struct("Morphed").field("name", &self.name).field("key", &self.key).finish();
+    }
+}
+
+/// Loads the model `{name}_type` and `{name}_id` point at.
+pub(crate) async fn morph_to<E: Executor>(db: &E, name: &str, kind: Value, key: Value) -> Result<Option<Morphed>> {
+    let Value::Text(kind) = kind else {
+        return Ok(None);
+    };
+
+    let eager = Eager { name: name.to_string(), nested: Vec::new(), constraint: None };
+
+    return Ok(morph_loader::<E>(&kind)?(db, vec![key], &eager).await?.into_iter().next());
+}
+
+/// A relation loaded with `Query::with`, and the relations to load on its models.
+#[derive(Clone)]
+pub struct Eager {
+    pub name: String,
+    pub nested: Vec<Eager>,
+    /// The `Scope<E, R>` passed to `Query::with_where`.
+    constraint: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Eager {
+    /// Adds the dotted `path` to the tree, the constraint goes to its last relation.
+    pub(crate) fn insert(eager: &mut Vec<Eager>, path: &str, constraint: Option<Arc<dyn Any + Send + Sync>>) {
+        let (name, nested) = match path.split_once('.') {
+            Some((name, nested)) => (name, Some(nested)),
+            None => (path, None),
+        };
+
+        let index = match eager.iter().position(|eager| eager.name == name) {
+            Some(index) => index,
+            None => {
+                eager.push(Eager { name: name.to_string(), nested: Vec::new(), constraint: None });
+                eager.len() - 1
+            },
+        };
+
+        match nested {
+            Some(nested) => Self::insert(&mut eager[index].nested, nested, constraint),
+            None => if constraint.is_some() {
+                eager[index].constraint = constraint;
+            },
+        }
+    }
+}
+
+/// A model loaded through a pivot table, with the pivot columns of its row.
+#[derive(Debug, Clone)]
+pub struct Pivoted<R> {
+    pub model: R,
+    pub pivot: HashMap<String, Value>,
+}
+
+/// The `R` rows linked to a model through a pivot table, returned by `Model::belongs_to_many`.
+///
+/// Derefs to the `Query` on the related table, so it can be filtered and fetched like any other query.
+pub struct BelongsToMany<'q, E: Executor, R> {
+    query: Query<'q, E, R>,
+    db: &'q Database<E>,
+    parent: std::result::Result<Value, Arc<anyhow::Error>>,
+    pivot: String,
+    foreign_pivot_key: String,
+    related_pivot_key: String,
+    pivot_columns: Vec<String>,
+}
+
+impl <'q, E, R>BelongsToMany<'q, E, R>
+where
+    E: Executor,
+    R: Model
+{
+    pub(crate) fn new(db: &'q Database<E>, parent: Result<Value>, pivot: &str, foreign_pivot_key: &str, related_pivot_key: &str) -> Self {
+        let mut query = R::query(db);
+
+        query.select(vec![&format!("{}.*", R::TABLE)]);
+        query.join(pivot, &format!("{}.{}", pivot, related_pivot_key), &format!("{}.{}", R::TABLE, R::PRIMARY_KEY));
+        let parent = parent.map_err(Arc::new);
+
+        query.where_value(&format!("{}.{}", pivot, foreign_pivot_key), parent.clone().map_err(|error| anyhow!("{:#}", error)));
+
+        return Self {
+            query: query,
+            db: db,
+            parent: parent,
+            pivot: pivot.to_string(),
+            foreign_pivot_key: foreign_pivot_key.to_string(),
+            related_pivot_key: related_pivot_key.to_string(),
+            pivot_columns: Vec::new(),
+        }
+    }
+
+    /// Extra pivot columns `all_with_pivot` reads next to the two keys.
+    pub fn with_pivot(&mut self, columns: Vec<&str>) -> &mut Self {
+        self.pivot_columns.extend(columns.iter().map(|column| column.to_string()));
+
+        return self;
+    }
+
+    pub async fn all_with_pivot(&mut self) -> Result<Vec<Pivoted<R>>>
+    where
+        R: for<'r> FromRow<'r, <E::T as sqlx::Database>::Row>
+    {
+        let columns = [self.foreign_pivot_key.clone(), self.related_pivot_key.clone()]
+            .into_iter()
+            .chain(self.pivot_columns.iter().cloned())
+            .collect::<Vec<_>>();
+
+        for (i, column) in columns.iter().enumerate() {
+            self.query.statement.query.select.push(format!("{}.{} AS {}", self.pivot, column, query::key_alias(i)));
+        }
+
+        self.query.apply_scopes();
+
+        let rows = self.db.executor.all::<Keyed<E::T, R>>(&self.query.statement).await?;
+
+        return rows.into_iter().map(|row| {
+            let mut model = row.row;
+
+            model.sync_original()?;
+
+            return Ok(Pivoted {
+                model: model,
+                pivot: columns.iter().cloned().zip(row.keys).collect(),
+            });
+        }).collect();
+    }
+
+    /// Inserts a pivot row for each of the related keys.
+    pub async fn attach<K: Into<Value>>(&self, ids: Vec<K>) -> Result<()> {
+        let transaction = self.db.transaction().await?;
+
+        for id in ids {
+            self.insert(&transaction, id.into()).await?;
+        }
+
+        return transaction.commit().await;
+    }
+
+    /// Deletes the pivot rows of the related keys.
+    pub async fn detach<K: Into<Value>>(&self, ids: Vec<K>) -> Result<()> {
+        let transaction = self.db.transaction().await?;
+
+        for id in ids {
+            self.delete(&transaction, id.into()).await?;
+        }
+
+        return transaction.commit().await;
+    }
+
+    /// Leaves the model linked to exactly the related keys, attaching and detaching as needed.
+    pub async fn sync<K: Into<Value>>(&self, ids: Vec<K>) -> Result<()> {
+        let ids = ids.into_iter().map(Into::into).collect::<Vec<Value>>();
+        let transaction = self.db.transaction().await?;
+        let current = self.current(&transaction).await?;
+
+        for id in current.iter().filter(|id| !contains(&ids, id)) {
+            self.delete(&transaction, id.clone()).await?;
+        }
+
+        for id in ids.iter().filter(|id| !contains(&current, id)) {
+            self.insert(&transaction, id.clone()).await?;
+        }
+
+        return transaction.commit().await;
+    }
+
+    /// Detaches the related keys that are attached and attaches the others.
+    pub async fn toggle<K: Into<Value>>(&self, ids: Vec<K>) -> Result<()> {
+        let transaction = self.db.transaction().await?;
+        let current = self.current(&transaction).await?;
+
+        for id in ids.into_iter().map(Into::into) {
+            if contains(&current, &id) {
+                self.delete(&transaction, id).await?;
+            } else {
+                self.insert(&transaction, id).await?;
+            }
+        }
+
+        return transaction.commit().await;
+    }
+
+    /// The key of the model the relation was read from, or the error its cast failed with.
+    fn parent(&self) -> Result<Value> {
+        return self.parent.clone().map_err(|error| anyhow!("{:#}", error));
+    }
+
+    async fn current(&self, db: &Database<E>) -> Result<Vec<Value>> {
+        let mut query = db.query(&self.pivot);
+
+        query.select(vec![&format!("{} AS {}", self.related_pivot_key, query::key_alias(0))]);
+        query.r#where(&self.foreign_pivot_key, "=", <E::T as Driver>::Argument::from(self.parent()?));
+
+        let rows = db.executor.all::<Keyed<E::T, ()>>(&query.statement).await?;
+
+        return Ok(rows.into_iter().flat_map(|row| row.keys).collect());
+    }
+
+    async fn insert(&self, db: &Database<E>, id: Value) -> Result<()> {
+        let mut query = db.query(&self.pivot);
+
+        query.bind_values(vec![(self.foreign_pivot_key.as_str(), self.parent()?), (self.related_pivot_key.as_str(), id)], false, true)?;
+
+        return db.executor.insert(&query.statement).await;
+    }
+
+    async fn delete(&self, db: &Database<E>, id: Value) -> Result<()> {
+        let mut query = db.query(&self.pivot);
+
+        query.r#where(&self.foreign_pivot_key, "=", <E::T as Driver>::Argument::from(self.parent()?));
+        query.r#where(&self.related_pivot_key, "=", <E::T as Driver>::Argument::from(id));
+
+        return query.delete().await;
+    }
+}
+
+impl <'q, E: Executor, R>Deref for BelongsToMany<'q, E, R> {
+    type Target = Query<'q, E, R>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.query;
+    }
+}
+
+impl <'q, E: Executor, R>DerefMut for BelongsToMany<'q, E, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        return &mut self.query;
+    }
+}
+
+fn contains(ids: &[Value], id: &Value) -> bool {
+    let id = hash(id);
+
+    return ids.iter().any(|other| hash(other) == id);
+}
+
+/// Loads the relations of the models, one query per relation.
+pub(crate) async fn load<E: Executor, M: Model>(db: &E, models: &mut [M], eager: &[Eager]) -> Result<()> {
+    if models.len() == 0 {
+        return Ok(());
+    }
+
+    for eager in eager {
+        M::eager_load(db, models, eager).await?;
+    }
+
+    return Ok(());
+}
+
+/// Loads `#[orm(has_many = "foreign_key")]` fields, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub async fn load_has_many<E, M, R, F>(db: &E, models: &mut [M], foreign_key: &str, eager: &Eager, set: F) -> Result<()>
+where
+    E: Executor,
+    M: Model,
+    R: Model + Decodable,
+    F: Fn(&mut M, Vec<R>)
+{
+    let keys = models.iter().map(M::key).collect::<Result<Vec<_>>>()?;
+    let related = fetch::<E, R, _>(db, eager, |query| {
+        query.where_in(&format!("{}.{}", R::TABLE, foreign_key), keys);
+    }).await?;
+
+    let mut grouped: HashMap<String, Vec<R>> = HashMap::new();
+
+    for (_, related) in related {
+        grouped.entry(hash(&related.value(foreign_key)?)).or_default().push(related);
+    }
+
+    for model in models.iter_mut() {
+        let related = grouped.remove(&hash(&model.key()?)).unwrap_or_default();
+
+        set(model, related);
+    }
+
+    return Ok(());
+}
+
+/// Loads `#[orm(has_one = "foreign_key")]` fields, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub async fn load_has_one<E, M, R, F>(db: &E, models: &mut [M], foreign_key: &str, eager: &Eager, set: F) -> Result<()>
+where
+    E: Executor,
+    M: Model,
+    R: Model + Decodable,
+    F: Fn(&mut M, Option<R>)
+{
+    return load_has_many::<E, M, R, _>(db, models, foreign_key, eager, |model, related| set(model, related.into_iter().next())).await;
+}
+
+/// Loads `#[orm(belongs_to = "foreign_key")]` fields, used by `#[derive(Model)]`.
+///
+/// Models sharing an owner each get a clone of it.
+#[doc(hidden)]
+pub async fn load_belongs_to<E, M, R, F>(db: &E, models: &mut [M], foreign_key: &str, eager: &Eager, set: F) -> Result<()>
+where
+    E: Executor,
+    M: Model,
+    R: Model + Decodable + Clone,
+    F: Fn(&mut M, Option<R>)
+{
+    let keys = models.iter().map(|model| model.value(foreign_key)).collect::<Result<Vec<_>>>()?;
+    let keys = keys.into_iter().filter(|key| !key.is_null()).collect::<Vec<_>>();
+    let related = fetch::<E, R, _>(db, eager, |query| {
+        query.where_in(&format!("{}.{}", R::TABLE, R::PRIMARY_KEY), keys);
+    }).await?;
+
+    let owners = related.into_iter()
+        .map(|(_, related)| Ok((hash(&related.key()?), related)))
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    for model in models.iter_mut() {
+        let owner = owners.get(&hash(&model.value(foreign_key)?)).cloned();
+
+        set(model, owner);
+    }
+
+    return Ok(());
+}
+
+/// Loads `#[orm(belongs_to_many(pivot = "...", foreign_key = "...", related_key = "..."))]` fields, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub async fn load_belongs_to_many<E, M, R, F>(db: &E, models: &mut [M], pivot: &str, foreign_pivot_key: &str, related_pivot_key: &str, eager: &Eager, set: F) -> Result<()>
+where
+    E: Executor,
+    M: Model,
+    R: Model + Decodable,
+    F: Fn(&mut M, Vec<R>)
+{
+    let keys = models.iter().map(M::key).collect::<Result<Vec<_>>>()?;
+    let related = fetch::<E, R, _>(db, eager, |query| {
+        query.select(vec![&format!("{}.*", R::TABLE), &format!("{}.{} AS {}", pivot, foreign_pivot_key, query::key_alias(0))]);
+        query.join(pivot, &format!("{}.{}", pivot, related_pivot_key), &format!("{}.{}", R::TABLE, R::PRIMARY_KEY));
+        query.where_in(&format!("{}.{}", pivot, foreign_pivot_key), keys);
+    }).await?;
+
+    let mut grouped: HashMap<String, Vec<R>> = HashMap::new();
+
+    for (keys, related) in related {
+        grouped.entry(hash(&keys[0])).or_default().push(related);
+    }
+
+    for model in models.iter_mut() {
+        let related = grouped.remove(&hash(&model.key()?)).unwrap_or_default();
+
+        set(model, related);
+    }
+
+    return Ok(());
+}
+
+/// Loads `#[orm(morph_many = "name")]` fields, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub async fn load_morph_many<E, M, R, F>(db: &E, models: &mut [M], name: &str, eager: &Eager, set: F) -> Result<()>
+where
+    E: Executor,
+    M: Model,
+    R: Model + Decodable,
+    F: Fn(&mut M, Vec<R>)
+{
+    let keys = models.iter().map(M::key).collect::<Result<Vec<_>>>()?;
+    let foreign_key = format!("{}_id", name);
+    let related = fetch::<E, R, _>(db, eager, |query| {
+        query.r#where(&format!("{}.{}_type", R::TABLE, name), "=", <E::T as Driver>::Argument::from(Value::from(M::MORPH_NAME)));
+        query.where_in(&format!("{}.{}", R::TABLE, foreign_key), keys);
+    }).await?;
+
+    let mut grouped: HashMap<String, Vec<R>> = HashMap::new();
+
+    for (_, related) in related {
+        grouped.entry(hash(&related.value(&foreign_key)?)).or_default().push(related);
+    }
+
+    for model in models.iter_mut() {
+        let related = grouped.remove(&hash(&model.key()?)).unwrap_or_default();
+
+        set(model, related);
+    }
+
+    return Ok(());
+}
+
+/// Loads `#[orm(morph_one = "name")]` fields, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub async fn load_morph_one<E, M, R, F>(db: &E, models: &mut [M], name: &str, eager: &Eager, set: F) -> Result<()>
+where
+    E: Executor,
+    M: Model,
+    R: Model + Decodable,
+    F: Fn(&mut M, Option<R>)
+{
+    return load_morph_many::<E, M, R, _>(db, models, name, eager, |model, related| set(model, related.into_iter().next())).await;
+}
+
+/// Loads `#[orm(morph_to)]` fields with one query per `{name}_type`, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub async fn load_morph_to<E, M, F>(db: &E, models: &mut [M], name: &str, eager: &Eager, set: F) -> Result<()>
+where
+    E: Executor,
+    M: Model,
+    F: Fn(&mut M, Option<Morphed>)
+{
+    let (kind, foreign_key) = (format!("{}_type", name), format!("{}_id", name));
+    let mut keys: Vec<(String, Vec<Value>)> = Vec::new();
+
+    for model in models.iter() {
+        let (Value::Text(kind), key) = (model.value(&kind)?, model.value(&foreign_key)?) else {
+            continue;
+        };
+
+        match keys.iter_mut().find(|(name, _)| *name == kind) {
+            Some((_, keys)) => keys.push(key),
+            None => keys.push((kind, vec![key])),
+        }
+    }
+
+    let mut morphed: HashMap<(String, String), Morphed> = HashMap::new();
+
+    for (kind, keys) in keys {
+        for related in morph_loader::<E>(&kind)?(db, keys, eager).await? {
+            morphed.insert((kind.clone(), hash(related.key())), related);
+        }
+    }
+
+    for model in models.iter_mut() {
+        let related = match model.value(&kind)? {
+            Value::Text(kind) => morphed.get(&(kind, hash(&model.value(&foreign_key)?))).cloned(),
+            _ => None,
+        };
+
+        set(model, related);
+    }
+
+    return Ok(());
+}
+
+/// Called by `#[derive(Model)]` for relations the model does not have.
+#[doc(hidden)]
+pub fn unknown<M: Model>(eager: &Eager) -> Result<()> {
+    return Err(anyhow!("`{}` has no relation `{}`", M::TABLE, eager.name));
+}
+
+/// Fetches the related models with their `key_alias` columns and loads their nested relations.
+async fn fetch<E, R, F>(db: &E, eager: &Eager, prepare: F) -> Result<Vec<(Vec<Value>, R)>>
+where
+    E: Executor,
+    R: Model + Decodable,
+    F: FnOnce(&mut Query<'_, E, R>)
+{
+    let mut query = model::query::<E, R>(db);
+
+    prepare(&mut query);
+
+    if let Some(constraint) = &eager.constraint {
+        let constraint = constraint.downcast_ref::<Scope<E, R>>()
+            .ok_or_else(|| anyhow!("the constraint on `{}` is not a query on `{}`", eager.name, R::TABLE))?;
+
+        query.scope(|query| constraint(query));
+    }
+
+    query.apply_scopes();
+
+    let rows = db.all::<Keyed<E::T, Decoded<E::T, R>>>(&query.statement).await?;
+    let mut keys = Vec::new();
+    let mut related = Vec::new();
+
+    for row in rows {
+        let mut model = row.row.row;
+
+        model.sync_original()?;
+        keys.push(row.keys);
+        related.push(model);
+    }
+
+    load(db, &mut related, &eager.nested).await?;
+
+    return Ok(keys.into_iter().zip(related).collect());
+}
+
+/// Keys are matched by their JSON form, so a `Uuid` matches the text it is stored as.
+fn hash(key: &Value) -> String {
+    return serde_json::Value::from(key.clone()).to_string();
+}
@@ -0,0 +1,65 @@
+use serde::{Serialize, Serializer};
+
+use crate::Model;
+
+/// Per-call changes to the `HIDDEN` and `VISIBLE` fields of a model.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Visibility {
+    visible: Vec<String>,
+    hidden: Vec<String>,
+}
+
+impl Visibility {
+    /// Whether the field is serialized, the per-call changes win over the model's lists.
+    pub fn shows(&self, field: &str, hidden: &[&str], visible: &[&str]) -> bool {
+        if self.hidden.iter().any(|hidden| hidden == field) {
+            return false;
+        }
+
+        if self.visible.iter().any(|visible| visible == field) {
+            return true;
+        }
+
+        if !visible.is_empty() {
+            return visible.contains(&field);
+        }
+
+        return !hidden.contains(&field);
+    }
+}
+
+/// A model serialized with some of its fields made visible or hidden, from `Model::make_visible` or `make_hidden`.
+#[derive(Debug, Clone)]
+pub struct Serialized<M> {
+    pub model: M,
+    visibility: Visibility,
+}
+
+impl <M: Model>Serialized<M> {
+    pub(crate) fn new(model: M) -> Self {
+        return Self {
+            model: model,
+            visibility: Visibility::default(),
+        };
+    }
+
+    pub fn make_visible(mut self, fields: &[&str]) -> Self {
+        self.visibility.hidden.retain(|field| !fields.contains(&field.as_str()));
+        self.visibility.visible.extend(fields.iter().map(|field| field.to_string()));
+
+        return self;
+    }
+
+    pub fn make_hidden(mut self, fields: &[&str]) -> Self {
+        self.visibility.visible.retain(|field| !fields.contains(&field.as_str()));
+        self.visibility.hidden.extend(fields.iter().map(|field| field.to_string()));
+
+        return self;
+    }
+}
+
+impl <M: Model + Serialize>Serialize for Serialized<M> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return self.model.serialize_visible(serializer, &self.visibility);
+    }
+}
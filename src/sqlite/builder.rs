@@ -2,7 +2,9 @@ use std::vec;
 
 use anyhow::{Ok, Result};
 
-use crate::query::{QueryBuilder, QueryStatement};
+use sqlx::Sqlite;
+
+use crate::{Driver, query::{QueryBuilder, QueryStatement, WhereQuery}};
 
 #[derive(Debug)]
 pub(crate) struct Builder<'q> {
@@ -19,23 +21,35 @@ impl <'q>QueryBuilder<'q> for Builder<'q> {
     fn insert(&self) -> Result<String> {
         let columns = self.statement.columns.clone().unwrap();
 
+        let upsert = match &self.statement.upsert {
+            Some((unique_by, update)) if update.len() == 0 => format!(" ON CONFLICT ({}) DO NOTHING", unique_by.join(", ")),
+            Some((unique_by, update)) => format!(
+                " ON CONFLICT ({}) DO UPDATE SET {}",
+                unique_by.join(", "),
+                update.iter().map(|column| format!("{} = excluded.{}", column, column)).collect::<Vec<_>>().join(", ")
+            ),
+            None => String::new(),
+        };
+
         return Ok(format!(
-            "INSERT INTO {} ({}) VALUES ({});",
+            "INSERT INTO {} ({}) VALUES ({}){};",
             self.statement.table,
             columns.join(", "),
-            std::iter::repeat("?").take(columns.len()).collect::<Vec<_>>().join(", ")
+            std::iter::repeat_n("?", columns.len()).collect::<Vec<_>>().join(", "),
+            upsert
         ));
     }
     
     fn update(&self) -> Result<String> {
         let mut sql = vec![
-            String::from(format!("UPDATE {}", self.statement.table)),
+            format!("UPDATE {}", self.statement.table),
             format!("SET {}", self.statement
                 .columns
                 .clone()
                 .unwrap()
                 .iter()
                 .map(|f| format!("{} = ?", f))
+                .chain(self.statement.raw_columns.iter().map(|(column, expression)| format!("{} = {}", column, expression)))
                 .collect::<Vec<_>>()
                 .join(", ")
             )
@@ -44,7 +58,7 @@ impl <'q>QueryBuilder<'q> for Builder<'q> {
         if self.statement.where_queries.len() != 0 {
             sql.extend([
                 "WHERE".to_string(),
-                    format!("{}", self.r#where().unwrap().as_str()),
+                    self.r#where().unwrap(),
             ]);
         }
 
@@ -52,12 +66,12 @@ impl <'q>QueryBuilder<'q> for Builder<'q> {
     }
     
     fn delete(&self) -> Result<String> {
-        let mut sql = vec![String::from(format!("DELETE FROM {}", self.statement.table))];
+        let mut sql = vec![format!("DELETE FROM {}", self.statement.table)];
 
         if self.statement.where_queries.len() != 0 {
             sql.extend([
                 "WHERE".to_string(),
-                    format!("{}", self.r#where().unwrap().as_str()),
+                    self.r#where().unwrap(),
             ]);
         }
 
@@ -67,22 +81,41 @@ impl <'q>QueryBuilder<'q> for Builder<'q> {
     fn query(&self) -> Result<String> {
         let mut sql = vec![
             "SELECT".to_string(),
-                format!("{}", self.select().unwrap().as_str()),
+                self.select().unwrap(),
             "FROM".to_string(),
-                format!("{}", self.statement.table),
+                self.statement.table.clone(),
         ];
 
         if self.statement.join.len() != 0 {
-            sql.push(format!("{}", self.join().unwrap().as_str()));
+            sql.push(self.join().unwrap());
         }
 
         if self.statement.where_queries.len() != 0 {
             sql.extend([
                 "WHERE".to_string(),
-                    format!("{}", self.r#where().unwrap().as_str()),
+                    self.r#where().unwrap(),
+            ]);
+        }
+
+        if self.statement.order_by.len() != 0 {
+            sql.extend([
+                "ORDER BY".to_string(),
+                    self.order_by().unwrap(),
             ]);
         }
 
+        if let Some(limit) = self.statement.limit {
+            sql.push(format!("LIMIT {}", limit));
+
+            if let Some(page) = self.statement.page.filter(|page| *page > 1) {
+                sql.push(format!("OFFSET {}", (page - 1) * limit));
+            }
+        }
+
+        if let Some(lock) = self.statement.lock.as_ref().and_then(Sqlite::lock) {
+            sql.push(lock);
+        }
+
         return Ok(sql.join(" "));
     }
 
@@ -111,9 +144,31 @@ impl <'q>QueryBuilder<'q> for Builder<'q> {
     }
 
     fn r#where(&self) -> Result<String> {
+        return self.conditions(&self.statement.where_queries);
+    }
+
+    fn order_by(&self) -> Result<String> {
+        return Ok(self.statement.order_by
+            .iter()
+            .map(|order| match order.order {
+                crate::query::Order::ASC => format!("{} ASC", order.column),
+                crate::query::Order::DESC => format!("{} DESC", order.column),
+            })
+            .collect::<Vec<_>>()
+            .join(", "));
+    }
+    
+    fn group_by(&self) -> Result<String> {
+        todo!()
+    }
+    
+}
+
+impl <'q>Builder<'q> {
+    fn conditions(&self, where_queries: &[WhereQuery]) -> Result<String> {
         let mut conditions: Vec<String> = Vec::new();
 
-        for where_query in &self.statement.where_queries {
+        for where_query in where_queries {
             if let Some(position) = &where_query.position {
                 match position {
                     crate::query::QueryPosition::AND => conditions.push(String::from("AND")),
@@ -122,12 +177,17 @@ impl <'q>QueryBuilder<'q> for Builder<'q> {
             }
 
             match &where_query.group {
-                Some(group) => {
-
-                },
+                Some(group) => conditions.push(format!("({})", self.conditions(group).unwrap())),
                 None => {
                     match where_query.operator.clone().unwrap().as_str().to_lowercase().as_str() {
                         "like" => conditions.push(format!("{} LIKE '%' || ? || '%'", where_query.column.clone().unwrap())),
+                        "is null" => conditions.push(format!("{} IS NULL", where_query.column.clone().unwrap())),
+                        "is not null" => conditions.push(format!("{} IS NOT NULL", where_query.column.clone().unwrap())),
+                        "raw" => conditions.push(where_query.column.clone().unwrap()),
+                        "in" => conditions.push(match where_query.values.unwrap_or(0) {
+                            0 => String::from("1 = 0"),
+                            values => format!("{} IN ({})", where_query.column.clone().unwrap(), vec!["?"; values].join(", ")),
+                        }),
                         _ => conditions.push(format!("{} {} ?", where_query.column.clone().unwrap(), where_query.operator.clone().unwrap())),
                     }
                 },
@@ -136,9 +196,4 @@ impl <'q>QueryBuilder<'q> for Builder<'q> {
 
         return Ok(conditions.join(" "));
     }
-    
-    fn group_by(&self) -> Result<String> {
-        todo!()
-    }
-    
 }
\ No newline at end of file
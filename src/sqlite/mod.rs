@@ -1,13 +1,28 @@
 mod builder;
+mod value;
+
+use std::sync::Arc;
 
 use anyhow::Result;
+use async_stream::try_stream;
+use futures::{TryStreamExt, stream::BoxStream};
 use sqlx::{Arguments, Pool, Sqlite};
+use tokio::sync::Mutex;
 
-use crate::{Executor, query::{Pagination, QueryBuilder, Statement, Total, WhereQuery}, sqlite::builder::Builder};
+use crate::{Driver, Executor, Value, query::{Connection, Pagination, QueryBuilder, SharedTransaction, Statement, Total, WhereQuery}, sqlite::builder::Builder};
 
 #[derive(Debug)]
 pub struct SQLite {
     db: Pool<Sqlite>,
+    transaction: Option<SharedTransaction<Sqlite>>,
+    /// The savepoint of a transaction begun in another, `0` for the outermost.
+    depth: usize,
+}
+
+impl SQLite {
+    async fn connection(&self) -> Result<Connection<'_, Sqlite>> {
+        return Connection::acquire(&self.db, &self.transaction).await;
+    }
 }
 
 impl Executor for SQLite {
@@ -16,69 +31,154 @@ impl Executor for SQLite {
     async fn new(url: &str) -> Self where Self: Sized {
         return Self {
             db: sqlx::SqlitePool::connect(url).await.unwrap(),
+            transaction: None,
+            depth: 0,
         }
     }
     
     fn db<'q>(&'q self) -> &'q Pool<Self::T> {
         return &self.db;
     }
+
+    async fn begin(&self) -> Result<Self> where Self: Sized {
+        if let Some(transaction) = &self.transaction {
+            let nested = Self {
+                db: self.db.clone(),
+                transaction: Some(transaction.clone()),
+                depth: self.depth + 1,
+            };
+
+            nested.execute(&format!("SAVEPOINT flyer_orm_{}", nested.depth)).await?;
+
+            return Ok(nested);
+        }
+
+        return Ok(Self {
+            db: self.db.clone(),
+            transaction: Some(Arc::new(Mutex::new(Some(self.db.begin().await?)))),
+            depth: 0,
+        });
+    }
+
+    async fn commit(&self) -> Result<()> {
+        if self.depth > 0 {
+            return self.execute(&format!("RELEASE SAVEPOINT flyer_orm_{}", self.depth)).await;
+        }
+
+        if let Some(transaction) = &self.transaction
+            && let Some(transaction) = transaction.lock().await.take()
+        {
+            transaction.commit().await?;
+        }
+
+        return Ok(());
+    }
+
+    async fn rollback(&self) -> Result<()> {
+        if self.depth > 0 {
+            self.execute(&format!("ROLLBACK TO SAVEPOINT flyer_orm_{}", self.depth)).await?;
+
+            return self.execute(&format!("RELEASE SAVEPOINT flyer_orm_{}", self.depth)).await;
+        }
+
+        if let Some(transaction) = &self.transaction
+            && let Some(transaction) = transaction.lock().await.take()
+        {
+            transaction.rollback().await?;
+        }
+
+        return Ok(());
+    }
     
-    fn to_sql<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<String> {
-        return Ok(Builder::new(&statement.query).query().unwrap());
+    fn to_sql<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<String> {
+        return Builder::new(&statement.query).query();
     }
 
     async fn execute<'q>(&self, sql: &'q str) -> Result<()> {
-        todo!();
+        sqlx::raw_sql(sql)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        return Ok(());
     }
     
-    async fn insert<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()> {
-        sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).insert().unwrap(), statement.arguments.clone())
-            .execute(&self.db)
-            .await
-            .unwrap();
+    async fn insert<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<()> {
+        sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).insert()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?;
         return Ok(());
     }
     
-    async fn insert_as<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<O>
+    async fn insert_as<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<O>
     where
         O: for<'r> sqlx::FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {   
-        let query_result = sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).insert().unwrap(), statement.arguments.clone())
-            .execute(&self.db)
-            .await
-            .unwrap();
+        // Without a key the row is read back by `rowid`, which tables created `WITHOUT ROWID` do not have.
+        if statement.query.key.is_none() {
+            let without_rowid = sqlx::query_scalar::<Self::T, bool>("SELECT wr FROM pragma_table_list WHERE name = ?")
+                .bind(&statement.query.table)
+                .fetch_optional(&mut *self.connection().await?)
+                .await?
+                .unwrap_or(false);
+
+            if without_rowid {
+                return Err(anyhow::anyhow!(
+                    "`{}` is a WITHOUT ROWID table, `insert_as` can only read its rows back by a key the model generates",
+                    statement.query.table
+                ));
+            }
+        }
+
+        let query_result = sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).insert()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?;
         
+        let (key, value) = match &statement.query.key {
+            Some((key, value)) => (key.clone(), value.clone()),
+            None => ("rowid".to_string(), Value::Int(query_result.last_insert_rowid())),
+        };
         let mut statement = Statement::<Self::T>::new(&statement.query.table);
 
         statement.query.where_queries.push(WhereQuery {
-            column: Some("rowid".to_string()),
+            column: Some(key),
             operator: Some("=".to_string()),
             position: None,
-            group: None
+            group: None,
+            values: None
         });
 
-        statement.arguments.add(query_result.last_insert_rowid()).unwrap();
+        Self::T::bind(&mut statement.arguments, value)?;
 
-        return Ok(self.first(&statement).await.unwrap());
+        return self.first(&statement).await;
     }
     
-    async fn update<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()> {
-        sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).update().unwrap(), statement.arguments.clone())
-            .execute(&self.db)
-            .await
-            .unwrap();
-        return Ok(());
+    async fn update<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<u64> {
+        return Ok(sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).update()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?
+            .rows_affected());
     }
     
-    async fn count<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<u64> {
-        return Ok(0);
+    async fn count<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<u64> {
+        let mut count = statement.duplicate();
+
+        count.query.order_by = Vec::new();
+        count.query.limit = None;
+        count.query.page = None;
+        count.query.lock = None;
+
+        // Counted over the query as a subquery, so values bound in its select, like `with_count`, keep their place.
+        let sql = format!("SELECT COUNT(*) as total FROM ({}) AS counted", self.to_sql(&count)?);
+
+        return Ok(sqlx::query_as_with::<Self::T, Total, _>(&sql, count.arguments.build()?)
+            .fetch_one(&mut *self.connection().await?)
+            .await?
+            .total);
     }
     
-    async fn delete<'q>(&self, statement: &'q Statement<'q, Self::T>) -> Result<()> {
-        sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).delete().unwrap(), statement.arguments.clone())
-            .execute(&self.db)
-            .await
-            .unwrap();
+    async fn delete<'q>(&self, statement: &Statement<'q, Self::T>) -> Result<()> {
+        sqlx::query_with::<Self::T, _>(&Builder::new(&statement.query).delete()?, statement.arguments.build()?)
+            .execute(&mut *self.connection().await?)
+            .await?;
         return Ok(());
     }
     
@@ -89,15 +189,12 @@ impl Executor for SQLite {
         let mut arguments: <Self::T as sqlx::Database>::Arguments<'q> = Default::default();
 
         for arg in args {
-            arguments.add(arg).unwrap();
+            arguments.add(arg).map_err(|e| anyhow::anyhow!(e))?;
         }
 
-        return Ok(
-            sqlx::query_as_with::<Self::T, O, _>(sql, arguments)
-                .fetch_all(&self.db)
-                .await
-                .unwrap()
-        )
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(sql, arguments)
+            .fetch_all(&mut *self.connection().await?)
+            .await?);
     }
     
     async fn query_one<'q, O, T: 'q + sqlx::Encode<'q, Self::T> + sqlx::Type<Self::T>>(&self, sql: &str, args: Vec<T>) -> Result<O>
@@ -107,68 +204,71 @@ impl Executor for SQLite {
         let mut arguments: <Self::T as sqlx::Database>::Arguments<'q> = Default::default();
 
         for arg in args {
-            arguments.add(arg).unwrap();
+            arguments.add(arg).map_err(|e| anyhow::anyhow!(e))?;
         }
 
-        return Ok(
-            sqlx::query_as_with::<Self::T, O, _>(sql, arguments)
-                .fetch_one(&self.db)
-                .await
-                .unwrap()
-        )
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(sql, arguments)
+            .fetch_one(&mut *self.connection().await?)
+            .await?);
     }
     
-    async fn first<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<O>
+    async fn first<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<O>
     where
         O: for<'r> sqlx::FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        return Ok(
-            sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement).unwrap(), statement.arguments.clone())
-                .fetch_one(&self.db)
-                .await
-                .unwrap()
-        );
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement)?, statement.arguments.build()?)
+            .fetch_one(&mut *self.connection().await?)
+            .await?);
     }
     
-    async fn all<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<Vec<O>>
+    async fn all<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<Vec<O>>
     where
         O: for<'r> sqlx::FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        return Ok(
-            sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement).unwrap(), statement.arguments.clone())
-                .fetch_all(&self.db)
-                .await
-                .unwrap(),
-        );
+        return Ok(sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement)?, statement.arguments.build()?)
+            .fetch_all(&mut *self.connection().await?)
+            .await?);
     }
     
-    // TODO: refactor...
-    async fn paginate<'q, O>(&self, statement: &'q Statement<'q, Self::T>) -> Result<Pagination<O>>
+    async fn paginate<'q, O>(&self, statement: &Statement<'q, Self::T>) -> Result<Pagination<O>>
     where
         O: for<'r> sqlx::FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized
     {
-        let mut query = statement.query.clone();
-
-        query.select = vec!["COUNT(*) as total".to_string()];
-        query.limit = None;
-        query.page = None;
-
-        return Ok(
-            Pagination {
-                page: statement.query.page.unwrap(),
-                per_page: statement.query.limit.unwrap(),
-                total: sqlx::query_as_with::<Self::T, Total, _>(&self.to_sql(statement).unwrap(), statement.arguments.clone())
-                    .fetch_one(&self.db)
-                    .await
-                    .unwrap()
-                    .total,
-                items: sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement).unwrap(), statement.arguments.clone())
-                    .fetch_all(&self.db)
-                    .await
-                    .unwrap(),
-            }
-        );
+        let total = self.count(statement).await?;
+
+        let items = sqlx::query_as_with::<Self::T, O, _>(&self.to_sql(statement)?, statement.arguments.build()?)
+            .fetch_all(&mut *self.connection().await?)
+            .await?;
+
+        return Ok(Pagination::new(items, total, statement.query.page.unwrap(), statement.query.limit.unwrap()));
     }
-}
 
+    fn stream<'q, O>(&'q self, statement: &'q Statement<'q, Self::T>) -> BoxStream<'q, Result<O>>
+    where
+        O: for<'r> sqlx::FromRow<'r, <Self::T as sqlx::Database>::Row> + Send + Unpin + Sized + 'q
+    {
+        return Box::pin(try_stream! {
+            let sql = self.to_sql(statement)?;
+            let mut connection = self.connection().await?;
+
+            // A transaction is one connection, its rows are read up front so the stream doesn't hold it from other queries.
+            if let Connection::Transaction(_) = connection {
+                let rows = sqlx::query_as_with::<Self::T, O, _>(&sql, statement.arguments.build()?)
+                    .fetch_all(&mut *connection)
+                    .await?;
 
+                drop(connection);
+
+                for row in rows {
+                    yield row;
+                }
+            } else {
+                let mut rows = sqlx::query_as_with::<Self::T, O, _>(&sql, statement.arguments.build()?).fetch(&mut *connection);
+
+                while let Some(row) = rows.try_next().await? {
+                    yield row;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use sqlx::{Decode, Encode, FromRow, Row, Sqlite, TypeInfo, ValueRef, encode::IsNull, error::BoxDynError, sqlite::{SqliteArgumentValue, SqliteRow, SqliteTypeInfo, SqliteValueRef}, types::Type};
+
+use crate::{Decodable, Driver, Value, query::Lock};
+
+impl Type<Sqlite> for Value {
+    fn type_info() -> SqliteTypeInfo {
+        return <str as Type<Sqlite>>::type_info();
+    }
+
+    fn compatible(_: &SqliteTypeInfo) -> bool {
+        return true;
+    }
+}
+
+/// Uuids are stored as hyphenated text and date times as `YYYY-MM-DD HH:MM:SS.SSSSSS`, which sorts with the
+/// `YYYY-MM-DD HH:MM:SS` of `CURRENT_TIMESTAMP`.
+impl<'q> Encode<'q, Sqlite> for Value {
+    fn encode_by_ref(&self, buf: &mut <Sqlite as sqlx::Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        return match self {
+            Value::Null => Ok(IsNull::Yes),
+            Value::Bool(value) => Encode::<Sqlite>::encode_by_ref(value, buf),
+            Value::Int(value) => Encode::<Sqlite>::encode_by_ref(value, buf),
+            Value::Float(value) => Encode::<Sqlite>::encode_by_ref(value, buf),
+            Value::Text(value) => Encode::<Sqlite>::encode_by_ref(value, buf),
+            Value::Bytes(value) => Encode::<Sqlite>::encode_by_ref(value, buf),
+            Value::Json(value) => Encode::<Sqlite>::encode_by_ref(&value.to_string(), buf),
+            Value::Uuid(value) => Encode::<Sqlite>::encode_by_ref(&value.hyphenated(), buf),
+            Value::DateTime(value) => Encode::<Sqlite>::encode_by_ref(&value.naive_utc(), buf),
+            Value::Date(value) => Encode::<Sqlite>::encode_by_ref(value, buf),
+            Value::Time(value) => Encode::<Sqlite>::encode_by_ref(value, buf),
+        };
+    }
+
+    fn produces(&self) -> Option<SqliteTypeInfo> {
+        return match self {
+            Value::Null => None,
+            Value::Bool(_) => Some(<bool as Type<Sqlite>>::type_info()),
+            Value::Int(_) => Some(<i64 as Type<Sqlite>>::type_info()),
+            Value::Float(_) => Some(<f64 as Type<Sqlite>>::type_info()),
+            Value::Bytes(_) => Some(<Vec<u8> as Type<Sqlite>>::type_info()),
+            _ => Some(<str as Type<Sqlite>>::type_info()),
+        };
+    }
+}
+
+/// Decodes by the storage class of the value, so text columns come back as `Value::Text`.
+impl<'r> Decode<'r, Sqlite> for Value {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        if value.is_null() {
+            return Ok(Value::Null);
+        }
+
+        return Ok(match value.type_info().name() {
+            "INTEGER" => Value::Int(<i64 as Decode<Sqlite>>::decode(value)?),
+            "REAL" => Value::Float(<f64 as Decode<Sqlite>>::decode(value)?),
+            "BLOB" => Value::Bytes(<Vec<u8> as Decode<Sqlite>>::decode(value)?),
+            _ => Value::Text(<String as Decode<Sqlite>>::decode(value)?),
+        });
+    }
+}
+
+/// A bound value, the `SqliteArgumentValue` it encodes to.
+#[derive(Clone)]
+pub struct Encoded<'q>(SqliteArgumentValue<'q>);
+
+impl Type<Sqlite> for Encoded<'_> {
+    fn type_info() -> SqliteTypeInfo {
+        return <str as Type<Sqlite>>::type_info();
+    }
+
+    fn compatible(_: &SqliteTypeInfo) -> bool {
+        return true;
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Encoded<'q> {
+    fn encode_by_ref(&self, buf: &mut <Sqlite as sqlx::Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        buf.push(self.0.clone());
+
+        return Ok(IsNull::No);
+    }
+}
+
+impl Driver for Sqlite {
+    type Argument = Value;
+    type Encoded<'q> = Encoded<'q>;
+
+    fn encode<'q, T: 'q + Encode<'q, Self> + Type<Self>>(value: T) -> Result<Encoded<'q>> {
+        let mut buf = Vec::new();
+
+        if let IsNull::Yes = value.encode(&mut buf).map_err(|e| anyhow::anyhow!(e))? {
+            return Ok(Encoded(SqliteArgumentValue::Null));
+        }
+
+        return Ok(Encoded(buf.pop().unwrap_or(SqliteArgumentValue::Null)));
+    }
+
+    fn get(row: &SqliteRow, column: &str) -> Result<Value> {
+        return Ok(row.try_get::<Value, _>(column)?);
+    }
+
+    fn text<'q, T: Encode<'q, Self> + Type<Self>>(value: &T) -> Option<String> {
+        let mut buf = Vec::new();
+
+        if let IsNull::Yes = value.encode_by_ref(&mut buf).ok()? {
+            return None;
+        }
+
+        return match buf.pop()? {
+            SqliteArgumentValue::Text(text) => Some(text.into_owned()),
+            _ => None,
+        };
+    }
+
+    /// SQLite locks the whole database, not rows, so the clause is left out.
+    fn lock(_: &Lock) -> Option<String> {
+        return None;
+    }
+
+    fn decode<M: Decodable>(row: &SqliteRow) -> Result<M, sqlx::Error> {
+        return <M as FromRow<SqliteRow>>::from_row(row);
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// A dynamically typed binding, lets arguments of different types live in the same `Vec`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    Json(serde_json::Value),
+    Uuid(Uuid),
+    DateTime(DateTime<Utc>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        return matches!(self, Value::Null);
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        return Value::Bool(value);
+    }
+}
+
+impl From<i8> for Value {
+    fn from(value: i8) -> Self {
+        return Value::Int(value.into());
+    }
+}
+
+impl From<i16> for Value {
+    fn from(value: i16) -> Self {
+        return Value::Int(value.into());
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        return Value::Int(value.into());
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        return Value::Int(value);
+    }
+}
+
+impl From<u8> for Value {
+    fn from(value: u8) -> Self {
+        return Value::Int(value.into());
+    }
+}
+
+impl From<u16> for Value {
+    fn from(value: u16) -> Self {
+        return Value::Int(value.into());
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        return Value::Int(value.into());
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        return Value::Float(value.into());
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        return Value::Float(value);
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        return Value::Text(value);
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        return Value::Text(value.to_string());
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        return Value::Bytes(value);
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(value: &[u8]) -> Self {
+        return Value::Bytes(value.to_vec());
+    }
+}
+
+impl From<Uuid> for Value {
+    fn from(value: Uuid) -> Self {
+        return Value::Uuid(value);
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(value: DateTime<Utc>) -> Self {
+        return Value::DateTime(value);
+    }
+}
+
+impl From<NaiveDateTime> for Value {
+    fn from(value: NaiveDateTime) -> Self {
+        return Value::DateTime(value.and_utc());
+    }
+}
+
+impl From<time::OffsetDateTime> for Value {
+    fn from(value: time::OffsetDateTime) -> Self {
+        return Value::DateTime(DateTime::from_timestamp_nanos(value.unix_timestamp_nanos() as i64));
+    }
+}
+
+impl From<time::PrimitiveDateTime> for Value {
+    fn from(value: time::PrimitiveDateTime) -> Self {
+        return value.assume_utc().into();
+    }
+}
+
+impl From<NaiveDate> for Value {
+    fn from(value: NaiveDate) -> Self {
+        return Value::Date(value);
+    }
+}
+
+impl From<NaiveTime> for Value {
+    fn from(value: NaiveTime) -> Self {
+        return Value::Time(value);
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        return match value {
+            Some(value) => value.into(),
+            None => Value::Null,
+        };
+    }
+}
+
+/// Scalars map to their own variant, arrays and objects are kept as `Value::Json`.
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        return match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(value) => Value::Bool(value),
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(value) => Value::Int(value),
+                None => Value::Float(number.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(value) => Value::Text(value),
+            value => Value::Json(value),
+        };
+    }
+}
+
+/// The reverse of `From<serde_json::Value>`, bytes become an array and uuids and dates their string form.
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        return match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(value) => value.into(),
+            Value::Int(value) => value.into(),
+            Value::Float(value) => value.into(),
+            Value::Text(value) => value.into(),
+            Value::Bytes(value) => value.into(),
+            Value::Json(value) => value,
+            Value::Uuid(value) => value.to_string().into(),
+            Value::DateTime(value) => value.naive_utc().to_string().into(),
+            Value::Date(value) => value.to_string().into(),
+            Value::Time(value) => value.to_string().into(),
+        };
+    }
+}
+
+/// The reverse of `Into<Value>`, reads a field back from the `Value` of its column.
+///
+/// Conversions are lenient where drivers differ, sqlite hands back dates, uuids and booleans as text or integers.
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self>;
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self> {
+        return Ok(value);
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Null => Ok(None),
+            value => Ok(Some(T::from_value(value)?)),
+        };
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Bool(value) => Ok(value),
+            Value::Int(value) => Ok(value != 0),
+            value => Err(mismatch("bool", &value)),
+        };
+    }
+}
+
+macro_rules! integer_from_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: Value) -> Result<Self> {
+                    return match value {
+                        Value::Int(value) => Ok(<$ty>::try_from(value)?),
+                        Value::Bool(value) => Ok(value as $ty),
+                        value => Err(mismatch(stringify!($ty), &value)),
+                    };
+                }
+            }
+        )*
+    };
+}
+
+integer_from_value!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+impl FromValue for f32 {
+    fn from_value(value: Value) -> Result<Self> {
+        return Ok(f64::from_value(value)? as f32);
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Float(value) => Ok(value),
+            Value::Int(value) => Ok(value as f64),
+            value => Err(mismatch("f64", &value)),
+        };
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Text(value) => Ok(value),
+            Value::Uuid(value) => Ok(value.to_string()),
+            Value::Json(value) => Ok(value.to_string()),
+            // The text SQLite stores a bound date time as, so a timestamp set by `save` matches the one read back.
+            Value::DateTime(value) => Ok(value.naive_utc().format("%F %T%.f").to_string()),
+            value => Err(mismatch("String", &value)),
+        };
+    }
+}
+
+impl FromValue for Vec<u8> {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Bytes(value) => Ok(value),
+            Value::Text(value) => Ok(value.into_bytes()),
+            value => Err(mismatch("Vec<u8>", &value)),
+        };
+    }
+}
+
+impl FromValue for Uuid {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Uuid(value) => Ok(value),
+            Value::Text(value) => Ok(Uuid::parse_str(&value)?),
+            Value::Bytes(value) => Ok(Uuid::from_slice(&value)?),
+            value => Err(mismatch("Uuid", &value)),
+        };
+    }
+}
+
+impl FromValue for serde_json::Value {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Text(value) => Ok(serde_json::from_str(&value)?),
+            value => Ok(value.into()),
+        };
+    }
+}
+
+impl FromValue for DateTime<Utc> {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::DateTime(value) => Ok(value),
+            Value::Int(value) => DateTime::from_timestamp(value, 0).ok_or_else(|| anyhow!("timestamp {} is out of range", value)),
+            Value::Text(value) => match DateTime::parse_from_rfc3339(&value) {
+                Ok(value) => Ok(value.to_utc()),
+                Err(_) => Ok(NaiveDateTime::from_value(Value::Text(value))?.and_utc()),
+            },
+            value => Err(mismatch("DateTime<Utc>", &value)),
+        };
+    }
+}
+
+impl FromValue for NaiveDateTime {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Text(value) => Ok(NaiveDateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S%.f")
+                .or_else(|_| NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S%.f"))?),
+            value => Ok(DateTime::<Utc>::from_value(value)?.naive_utc()),
+        };
+    }
+}
+
+impl FromValue for NaiveDate {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Date(value) => Ok(value),
+            Value::Text(value) => Ok(NaiveDate::parse_from_str(&value, "%Y-%m-%d")?),
+            value => Err(mismatch("NaiveDate", &value)),
+        };
+    }
+}
+
+impl FromValue for NaiveTime {
+    fn from_value(value: Value) -> Result<Self> {
+        return match value {
+            Value::Time(value) => Ok(value),
+            Value::Text(value) => Ok(NaiveTime::parse_from_str(&value, "%H:%M:%S%.f")?),
+            value => Err(mismatch("NaiveTime", &value)),
+        };
+    }
+}
+
+impl FromValue for time::OffsetDateTime {
+    fn from_value(value: Value) -> Result<Self> {
+        let value = DateTime::<Utc>::from_value(value)?;
+
+        return Ok(time::OffsetDateTime::from_unix_timestamp_nanos(value.timestamp_nanos_opt().unwrap_or_default() as i128)?);
+    }
+}
+
+impl FromValue for time::PrimitiveDateTime {
+    fn from_value(value: Value) -> Result<Self> {
+        let value = time::OffsetDateTime::from_value(value)?;
+
+        return Ok(time::PrimitiveDateTime::new(value.date(), value.time()));
+    }
+}
+
+fn mismatch(expected: &str, value: &Value) -> anyhow::Error {
+    return anyhow!("expected {}, found {:?}", expected, value);
+}
+
+/// Takes a column out of a row read into `Value`s, used by `#[derive(Model)]`.
+#[doc(hidden)]
+pub fn from_column<T: FromValue>(values: &mut HashMap<String, Value>, column: &str) -> Result<T> {
+    return T::from_value(values.remove(column).unwrap_or(Value::Null)).map_err(|e| anyhow!("column `{}`: {}", column, e));
+}
+
+/// Column and value pairs accepted by `insert_with` and `update_with`.
+///
+/// Maps of [`Value`] keep their exact types, any other `Serialize` struct or map goes through `serde_json`.
+/// The marker parameter only keeps the two kinds of impls apart and is always inferred.
+pub trait IntoValues<M> {
+    fn into_values(self) -> Result<Vec<(String, Value)>>;
+}
+
+#[doc(hidden)]
+pub struct SerializeValues;
+
+#[doc(hidden)]
+pub struct MapValues;
+
+impl<T: Serialize> IntoValues<SerializeValues> for T {
+    fn into_values(self) -> Result<Vec<(String, Value)>> {
+        let serde_json::Value::Object(map) = serde_json::to_value(self)? else {
+            return Err(anyhow!("values must serialize to a map or struct"));
+        };
+
+        return Ok(map.into_iter().map(|(column, value)| (column, value.into())).collect());
+    }
+}
+
+impl<K: Into<String>> IntoValues<MapValues> for HashMap<K, Value> {
+    fn into_values(self) -> Result<Vec<(String, Value)>> {
+        return Ok(self.into_iter().map(|(column, value)| (column.into(), value)).collect());
+    }
+}
+
+impl<K: Into<String>> IntoValues<MapValues> for BTreeMap<K, Value> {
+    fn into_values(self) -> Result<Vec<(String, Value)>> {
+        return Ok(self.into_iter().map(|(column, value)| (column.into(), value)).collect());
+    }
+}
+
+impl<K: Into<String>> IntoValues<MapValues> for Vec<(K, Value)> {
+    fn into_values(self) -> Result<Vec<(String, Value)>> {
+        return Ok(self.into_iter().map(|(column, value)| (column.into(), value)).collect());
+    }
+}
@@ -0,0 +1,128 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{TimeZone, Utc};
+use flyer_orm::{Model, Original, Value, cast::{self, Cast}};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+#[derive(Debug, Clone, Model)]
+#[orm(table = "documents")]
+struct Document<T: Serialize + DeserializeOwned + Clone + Send + Unpin + 'static> {
+    id: i64,
+    #[orm(cast = "json")]
+    body: T,
+    #[sqlx(skip)]
+    original: Original,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Open,
+    Closed,
+}
+
+#[derive(Debug, Clone, Model)]
+#[orm(table = "tasks")]
+struct Task {
+    id: i64,
+    #[orm(cast = "enum")]
+    status: Status,
+    #[orm(cast = "bool")]
+    done: bool,
+    #[orm(cast = "datetime")]
+    due: chrono::DateTime<Utc>,
+    #[orm(cast = "comma")]
+    tags: Vec<String>,
+    #[sqlx(skip)]
+    original: Original,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE documents (id INTEGER PRIMARY KEY, body TEXT NOT NULL);
+    CREATE TABLE tasks (id INTEGER PRIMARY KEY, status TEXT NOT NULL, done INTEGER NOT NULL, due TEXT NOT NULL, tags TEXT);
+";
+
+#[tokio::test]
+async fn reads_generic_models_with_casts() {
+    let db = common::database("casts_generic", SCHEMA).await;
+
+    let created = Document::<Vec<String>>::create(&db, HashMap::from([("body", Value::from(r#"["a","b"]"#))])).await.unwrap();
+    let found = Document::<Vec<String>>::find(&db, created.id).await.unwrap().unwrap();
+
+    assert_eq!(found.body, vec!["a".to_string(), "b".to_string()]);
+}
+
+#[tokio::test]
+async fn fails_saves_a_cast_cannot_encode() {
+    let db = common::database("casts_encode", SCHEMA).await;
+
+    let mut document = Document::<BTreeMap<Vec<u8>, String>>::create(&db, HashMap::from([("body", Value::from("{}"))])).await.unwrap();
+
+    document.body.insert(vec![1], "one".to_string());
+
+    let error = document.save(&db).await.unwrap_err();
+
+    assert!(error.to_string().contains("key must be a string"), "{}", error);
+    assert!(document.values().is_err());
+}
+
+#[tokio::test]
+async fn round_trips_the_built_in_casts() {
+    let db = common::database("casts_built_in", SCHEMA).await;
+
+    let due = Utc.with_ymd_and_hms(2026, 10, 19, 8, 30, 0).unwrap();
+    let created = Task::create(&db, HashMap::from([
+        ("status", Value::from("open")),
+        ("done", Value::from("yes")),
+        ("due", Value::from("2026-10-19T08:30:00Z")),
+        ("tags", Value::from("a,b")),
+    ])).await.unwrap();
+
+    assert_eq!((created.status, created.done, created.due, created.tags), (Status::Open, true, due, vec!["a".to_string(), "b".to_string()]));
+
+    let mut task = Task::find(&db, created.id).await.unwrap().unwrap();
+
+    task.status = Status::Closed;
+    task.done = false;
+    task.tags = Vec::new();
+    task.save(&db).await.unwrap();
+
+    let stored = db.query("tasks").select(vec!["status", "done", "tags"]).first::<(String, i64, String)>().await.unwrap();
+
+    assert_eq!(stored, ("closed".to_string(), 0, String::new()));
+
+    let found = Task::find(&db, created.id).await.unwrap().unwrap();
+
+    assert_eq!((found.status, found.done, found.due, found.tags), (Status::Closed, false, due, Vec::<String>::new()));
+
+    db.execute("UPDATE tasks SET done = 'maybe'").await.unwrap();
+
+    let error = Task::find(&db, created.id).await.unwrap_err();
+
+    assert!(format!("{:#}", error).contains("column `done`: expected a boolean"), "{:#}", error);
+}
+
+#[test]
+fn fails_to_decode_values_a_cast_does_not_read() {
+    assert_eq!(<cast::Enum as Cast<Status>>::encode(&Status::Open).unwrap(), Value::from("open"));
+    assert!(<cast::Enum as Cast<Status>>::decode(Value::from("archived")).is_err());
+
+    assert!(<cast::Bool as Cast<bool>>::decode(Value::from("T")).unwrap());
+    assert!(!<cast::Bool as Cast<Option<bool>>>::decode(Value::Int(0)).unwrap().unwrap());
+    assert!(<cast::Bool as Cast<bool>>::decode(Value::from("maybe")).is_err());
+
+    let due = <cast::DateTime as Cast<chrono::DateTime<Utc>>>::decode(Value::Int(0)).unwrap();
+
+    assert_eq!(<cast::DateTime as Cast<chrono::DateTime<Utc>>>::encode(&due).unwrap(), Value::DateTime(due));
+    assert!(<cast::DateTime as Cast<chrono::DateTime<Utc>>>::decode(Value::from("tomorrow")).is_err());
+
+    assert_eq!(<cast::Comma as Cast<Vec<String>>>::decode(Value::Null).unwrap(), Vec::<String>::new());
+    assert!(<cast::Comma as Cast<Vec<String>>>::decode(Value::Int(1)).is_err());
+
+    assert_eq!(<cast::Json as Cast<String>>::decode(Value::from(r#""draft""#)).unwrap(), "draft");
+    assert!(<cast::Json as Cast<String>>::decode(Value::from("draft")).is_err());
+}
@@ -0,0 +1,40 @@
+#![allow(dead_code, clippy::needless_return)]
+
+use flyer_orm::{Database, mysql::MySQL, postgres::Postgres, sqlite::SQLite};
+
+/// A fresh SQLite database file for a test, created with `schema`.
+pub async fn database(name: &str, schema: &str) -> Database<SQLite> {
+    let path = std::env::temp_dir().join(format!("flyer-orm-{}.db", name));
+    let _ = std::fs::remove_file(&path);
+    let db = Database::new(&format!("sqlite://{}?mode=rwc", path.display())).await;
+
+    db.execute(schema).await.unwrap();
+
+    return db;
+}
+
+/// The database of `POSTGRES_URL` created with `schema`, `None` skips the test when it isn't set.
+///
+/// Tests of a file run at the same time, so each one creates tables of its own.
+pub async fn postgres(schema: &str) -> Option<Database<Postgres>> {
+    let url = std::env::var("POSTGRES_URL").ok()?;
+    let db = Database::new(&url).await;
+
+    db.execute(schema).await.unwrap();
+
+    return Some(db);
+}
+
+/// The database of `MYSQL_URL` created with `schema`, `None` skips the test when it isn't set.
+///
+/// MySQL runs one statement at a time, so `schema` is split on `;`.
+pub async fn mysql(schema: &str) -> Option<Database<MySQL>> {
+    let url = std::env::var("MYSQL_URL").ok()?;
+    let db = Database::new(&url).await;
+
+    for statement in schema.split(';').filter(|statement| !statement.trim().is_empty()) {
+        db.execute(statement).await.unwrap();
+    }
+
+    return Some(db);
+}
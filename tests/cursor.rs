@@ -0,0 +1,67 @@
+mod common;
+
+use flyer_orm::query::Order;
+
+#[derive(Debug, sqlx::FromRow)]
+struct Event {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Token {
+    name: String,
+}
+
+#[tokio::test]
+async fn pages_forward_and_back() {
+    let db = common::database("cursor", "
+        CREATE TABLE events (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        INSERT INTO events (name) VALUES ('a'), ('b'), ('c'), ('d'), ('e');
+    ").await;
+
+    let mut query = db.query("events");
+    let first = query.order_by("id", Order::ASC).cursor_paginate::<Event>(2, None).await.unwrap();
+
+    assert_eq!(first.items.iter().map(|event| event.name.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+    assert!(first.prev_cursor.is_none());
+
+    let mut query = db.query("events");
+    let second = query.order_by("id", Order::ASC).cursor_paginate::<Event>(2, first.next_cursor.as_deref()).await.unwrap();
+
+    assert_eq!(second.items.iter().map(|event| event.id).collect::<Vec<_>>(), vec![3, 4]);
+
+    let mut query = db.query("events");
+    let back = query.order_by("id", Order::ASC).cursor_paginate::<Event>(2, second.prev_cursor.as_deref()).await.unwrap();
+
+    assert_eq!(back.items.iter().map(|event| event.id).collect::<Vec<_>>(), vec![1, 2]);
+
+    let mut query = db.query("events");
+    assert!(query.order_by("id", Order::ASC).cursor_paginate::<Event>(2, Some("not a cursor")).await.is_err());
+}
+
+#[tokio::test]
+async fn keeps_the_type_of_postgres_keys() {
+    let Some(db) = common::postgres("
+        DROP TABLE IF EXISTS pg_tokens;
+        CREATE TABLE pg_tokens (id UUID PRIMARY KEY, created_at TIMESTAMPTZ NOT NULL, name TEXT NOT NULL);
+        INSERT INTO pg_tokens (id, created_at, name) VALUES
+            ('00000000-0000-0000-0000-000000000001', '2024-01-01 00:00:00+00', 'a'),
+            ('00000000-0000-0000-0000-000000000002', '2024-01-01 00:00:00+00', 'b'),
+            ('00000000-0000-0000-0000-000000000003', '2024-01-02 00:00:00+00', 'c');
+    ").await else {
+        return;
+    };
+
+    let mut query = db.query("pg_tokens");
+    query.order_by("created_at", Order::ASC).order_by("id", Order::ASC);
+    let first = query.cursor_paginate::<Token>(2, None).await.unwrap();
+
+    assert_eq!(first.items.iter().map(|token| token.name.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+
+    let mut query = db.query("pg_tokens");
+    query.order_by("created_at", Order::ASC).order_by("id", Order::ASC);
+    let second = query.cursor_paginate::<Token>(2, first.next_cursor.as_deref()).await.unwrap();
+
+    assert_eq!(second.items.iter().map(|token| token.name.as_str()).collect::<Vec<_>>(), vec!["c"]);
+}
@@ -0,0 +1,125 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::collections::HashMap;
+
+use flyer_orm::{Model, Original, Value, encryption};
+use tokio::sync::{Mutex, MutexGuard};
+
+#[derive(Debug, Clone, Model)]
+#[orm(table = "members")]
+struct Member {
+    id: i64,
+    #[orm(encrypted, blind_index = "email_index")]
+    email: String,
+    name: String,
+    #[sqlx(skip)]
+    original: Original,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE members (id INTEGER PRIMARY KEY, email TEXT NOT NULL, email_index TEXT, name TEXT NOT NULL);
+";
+
+/// The keys are global, the guard keeps a test from changing them while another runs.
+static KEYS: Mutex<()> = Mutex::const_new(());
+
+async fn keys() -> MutexGuard<'static, ()> {
+    let guard = KEYS.lock().await;
+
+    encryption::set_keys(&[("1", [7; 32])]);
+    encryption::set_index_key([9; 32]);
+
+    return guard;
+}
+
+fn member(email: &str) -> HashMap<&'static str, Value> {
+    return HashMap::from([("email", Value::from(email)), ("name", Value::from("ann"))]);
+}
+
+#[tokio::test]
+async fn encrypts_with_random_nonces_and_matches_the_blind_index() {
+    let _keys = keys().await;
+
+    let db = common::database("encryption", SCHEMA).await;
+
+    Member::create(&db, member("ann@example.com")).await.unwrap();
+    Member::create(&db, member("ann@example.com")).await.unwrap();
+
+    let stored = db.query("members").select(vec!["email", "email_index"]).all::<(String, String)>().await.unwrap();
+
+    assert!(stored[0].0.starts_with("enc:1:"));
+    assert_ne!(stored[0].0, stored[1].0);
+    assert_eq!(stored[0].1, stored[1].1);
+
+    let found = Member::query(&db).r#where("email", "=", "ann@example.com").all().await.unwrap();
+
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].email, "ann@example.com");
+    assert_eq!(Member::query(&db).where_in("email", vec!["ann@example.com", "bob@example.com"]).all().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn compares_encrypted_fields_as_plain_text() {
+    let _keys = keys().await;
+
+    let db = common::database("encryption_dirty", SCHEMA).await;
+
+    let mut found = Member::create(&db, member("ann@example.com")).await.unwrap();
+
+    assert!(!found.is_dirty());
+    assert_eq!(found.value("email").unwrap(), Value::from("ann@example.com"));
+
+    found.email = "bob@example.com".to_string();
+
+    assert_eq!(found.dirty_fields(), vec!["email"]);
+
+    found.save(&db).await.unwrap();
+
+    assert_eq!(Member::query(&db).r#where("email", "=", "bob@example.com").all().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn fails_queries_on_values_that_cannot_be_indexed() {
+    let _keys = keys().await;
+
+    let db = common::database("encryption_errors", SCHEMA).await;
+
+    let error = Member::query(&db).where_in("email", vec!["enc:unknown:AAAA"]).all().await.unwrap_err();
+
+    assert!(error.to_string().contains("no encryption key `unknown`"), "{}", error);
+}
+
+#[tokio::test]
+async fn reads_rows_of_rotated_keys_and_plain_text() {
+    let _keys = keys().await;
+
+    let db = common::database("encryption_rotation", SCHEMA).await;
+
+    Member::create(&db, member("ann@example.com")).await.unwrap();
+    db.execute("INSERT INTO members (email, name) VALUES ('bob@example.com', 'bob')").await.unwrap();
+
+    encryption::set_keys(&[("2", [8; 32]), ("1", [7; 32])]);
+
+    let mut ann = Member::query(&db).r#where("email", "=", "ann@example.com").first_or_fail().await.unwrap();
+    let mut bob = Member::find_or_fail(&db, 2).await.unwrap();
+
+    assert_eq!((ann.email.as_str(), bob.email.as_str()), ("ann@example.com", "bob@example.com"));
+
+    ann.email = "anna@example.com".to_string();
+    ann.save(&db).await.unwrap();
+    bob.email = "robert@example.com".to_string();
+    bob.save(&db).await.unwrap();
+
+    let mut query = db.query("members");
+    let stored = query.select(vec!["email"]).all::<(String,)>().await.unwrap();
+
+    assert!(stored.iter().all(|(email,)| email.starts_with("enc:2:")), "{:?}", stored);
+
+    encryption::set_keys(&[("2", [8; 32])]);
+
+    let found = Member::query(&db).where_in("email", vec!["anna@example.com", "robert@example.com"]).all().await.unwrap();
+
+    assert_eq!(found.iter().map(|member| member.email.as_str()).collect::<Vec<_>>(), vec!["anna@example.com", "robert@example.com"]);
+}
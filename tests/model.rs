@@ -0,0 +1,698 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::collections::HashMap;
+
+use flyer_orm::{Attributes, MassAssignment, Model, ModelNotFound, Observer, Original, StaleModel, Value, anyhow, query::Order, sqlite::SQLite};
+use serde::Serialize;
+use serde_json::json;
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "users")]
+struct User {
+    id: i64,
+    name: String,
+    email: Option<String>,
+    #[sqlx(skip)]
+    original: Original,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "notes", soft_deletes)]
+struct Note {
+    id: i64,
+    title: String,
+    deleted_at: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "posts", timestamps)]
+struct Post {
+    id: i64,
+    title: String,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "tokens", key_type = "ulid")]
+struct Token {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "articles")]
+struct Article {
+    id: i64,
+    title: String,
+    #[orm(version)]
+    version: i64,
+    #[sqlx(skip)]
+    original: Original,
+}
+
+/// Only used by the scopes test, global scopes apply to every query of a model in every test running alongside.
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "users")]
+struct Member {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "logins", hidden("password"))]
+struct Login {
+    id: i64,
+    name: String,
+    password: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "logins", visible("id", "name"))]
+struct Profile {
+    id: i64,
+    #[orm(serialize_as = "displayName")]
+    name: String,
+    password: String,
+}
+
+/// Only used by the scoped updates test, for the same reason as `Member`.
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "projects")]
+struct Project {
+    id: i64,
+    tenant_id: i64,
+    name: String,
+}
+
+/// Observed by the hooks test, observers run for every save of a model in every test running alongside.
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "users")]
+struct Account {
+    id: i64,
+    name: String,
+    email: Option<String>,
+    #[sqlx(skip)]
+    original: Original,
+}
+
+struct KeepEmail;
+
+impl Observer<Account> for KeepEmail {
+    fn updating(&self, attributes: &mut Attributes) -> anyhow::Result<()> {
+        attributes.remove("email");
+
+        if let Some(Value::Text(name)) = attributes.get("name") {
+            attributes.set("name", name.to_uppercase());
+        }
+
+        return Ok(());
+    }
+}
+
+/// Observed by the aborted hooks test.
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "entries", soft_deletes)]
+struct Entry {
+    id: i64,
+    name: String,
+    deleted_at: Option<String>,
+}
+
+/// Fails the hook an entry is named after.
+struct FailNamed;
+
+impl FailNamed {
+    fn fail(hook: &str, name: &str) -> anyhow::Result<()> {
+        if hook == name {
+            return Err(anyhow::anyhow!("{} failed", hook));
+        }
+
+        return Ok(());
+    }
+}
+
+impl Observer<Entry> for FailNamed {
+    fn creating(&self, attributes: &mut Attributes) -> anyhow::Result<()> {
+        return match attributes.get("name") {
+            Some(Value::Text(name)) => Self::fail("creating", name),
+            _ => Ok(()),
+        };
+    }
+
+    fn created(&self, model: &Entry) -> anyhow::Result<()> {
+        return Self::fail("created", &model.name);
+    }
+
+    fn deleting(&self, model: &Entry) -> anyhow::Result<()> {
+        return Self::fail("deleting", &model.name);
+    }
+
+    fn deleted(&self, model: &Entry) -> anyhow::Result<()> {
+        return Self::fail("deleted", &model.name);
+    }
+
+    fn restored(&self, model: &Entry) -> anyhow::Result<()> {
+        return Self::fail("restored", &model.name);
+    }
+}
+
+/// Implemented by hand, without `set_value`.
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct Handmade {
+    id: i64,
+    name: String,
+}
+
+impl Model for Handmade {
+    const TABLE: &'static str = "users";
+    const PRIMARY_KEY: &'static str = "id";
+    const COLUMNS: &'static [&'static str] = &["id", "name"];
+
+    fn values(&self) -> anyhow::Result<Vec<(&'static str, Value)>> {
+        return Ok(vec![("id", Value::Int(self.id)), ("name", Value::from(self.name.as_str()))]);
+    }
+
+    fn from_values(values: Vec<(String, Value)>) -> anyhow::Result<Self> {
+        let mut model = Self { id: 0, name: String::new() };
+
+        for (column, value) in values {
+            match (column.as_str(), value) {
+                ("id", Value::Int(id)) => model.id = id,
+                ("name", Value::Text(name)) => model.name = name,
+                _ => {}
+            }
+        }
+
+        return Ok(model);
+    }
+}
+
+struct Rejected;
+
+impl Observer<Handmade> for Rejected {
+    fn updating(&self, attributes: &mut Attributes) -> anyhow::Result<()> {
+        attributes.set("name", "changed");
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "settings", guarded("locked"))]
+struct Setting {
+    id: i64,
+    key: String,
+    value: String,
+    locked: bool,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "users", fillable("name"))]
+struct Signup {
+    id: i64,
+    name: String,
+    email: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "users", fillable("name"), reject_guarded)]
+struct StrictSignup {
+    id: i64,
+    name: String,
+    email: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Rename {
+    name: String,
+    email: Option<String>,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, email TEXT);
+    CREATE TABLE notes (id INTEGER PRIMARY KEY, title TEXT NOT NULL, deleted_at TEXT);
+    CREATE TABLE posts (id INTEGER PRIMARY KEY, title TEXT NOT NULL, created_at TEXT, updated_at TEXT);
+    CREATE TABLE articles (id INTEGER PRIMARY KEY, title TEXT NOT NULL, version INTEGER NOT NULL);
+    CREATE TABLE tokens (id TEXT PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE entries (id INTEGER PRIMARY KEY, name TEXT NOT NULL, deleted_at TEXT);
+    CREATE TABLE logins (id INTEGER PRIMARY KEY, name TEXT NOT NULL, password TEXT NOT NULL);
+    CREATE TABLE projects (id INTEGER PRIMARY KEY, tenant_id INTEGER NOT NULL, name TEXT NOT NULL);
+    CREATE TABLE settings (id INTEGER PRIMARY KEY, key TEXT NOT NULL UNIQUE, value TEXT NOT NULL, locked BOOLEAN NOT NULL DEFAULT 0);
+";
+
+fn user(name: &str) -> HashMap<&'static str, Value> {
+    return HashMap::from([("name", Value::from(name))]);
+}
+
+#[tokio::test]
+async fn derives_the_model() {
+    assert_eq!((User::TABLE, User::PRIMARY_KEY), ("users", "id"));
+    assert_eq!(User::COLUMNS, &["id", "name", "email"]);
+
+    let model = User::from_values(vec![
+        ("id".to_string(), Value::Int(1)),
+        ("name".to_string(), Value::from("ann")),
+        ("email".to_string(), Value::Null),
+    ]).unwrap();
+
+    assert_eq!(model.values().unwrap(), vec![("id", Value::Int(1)), ("name", Value::from("ann")), ("email", Value::Null)]);
+    assert_eq!(model.key().unwrap(), Value::Int(1));
+}
+
+#[tokio::test]
+async fn creates_finds_saves_and_deletes() {
+    let db = common::database("model", SCHEMA).await;
+
+    let ann = User::create(&db, user("ann")).await.unwrap();
+
+    assert_eq!((ann.id, ann.name.as_str()), (1, "ann"));
+    assert_eq!(User::find(&db, 1).await.unwrap().unwrap().name, "ann");
+    assert!(User::find(&db, 2).await.unwrap().is_none());
+    assert!(User::find_or_fail(&db, 2).await.unwrap_err().is::<ModelNotFound>());
+
+    let mut bob = User { id: 0, name: "bob".to_string(), email: None, original: Original::default() };
+    bob.save(&db).await.unwrap();
+
+    assert_eq!(bob.id, 2);
+
+    bob.email = Some("bob@example.com".to_string());
+
+    assert_eq!(bob.dirty_fields(), vec!["email"]);
+
+    bob.save(&db).await.unwrap();
+
+    assert!(!bob.is_dirty());
+    assert_eq!(User::find(&db, 2).await.unwrap().unwrap().email.as_deref(), Some("bob@example.com"));
+
+    bob.delete(&db).await.unwrap();
+
+    assert_eq!(User::all(&db).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn returns_database_errors() {
+    let db = common::database("model_errors", SCHEMA).await;
+
+    assert!(User::query(&db).r#where("missing", "=", 1).all().await.unwrap_err().to_string().contains("no such column"));
+    assert!(db.query("users").first::<(i64,)>().await.is_err());
+    assert!(db.query("users").query_one::<(i64,), i64>("SELECT id FROM users WHERE id = ?", vec![1]).await.is_err());
+}
+
+#[tokio::test]
+async fn saves_models_with_a_key_set_by_the_client() {
+    let db = common::database("model_keys", SCHEMA).await;
+
+    let mut user = User { id: 10, name: "ann".to_string(), email: None, original: Original::default() };
+
+    assert!(!user.exists());
+
+    user.save(&db).await.unwrap();
+
+    assert!(user.exists());
+    assert_eq!(User::find_or_fail(&db, 10).await.unwrap().name, "ann");
+
+    // Without an `Original` field a set key reads as an existing row, saving one that isn't there fails.
+    let mut member = Member { id: 20, name: "bob".to_string() };
+
+    assert!(member.exists());
+    assert!(member.save(&db).await.unwrap_err().is::<ModelNotFound>());
+}
+
+#[tokio::test]
+async fn updates_from_maps_and_structs() {
+    let db = common::database("model_update", SCHEMA).await;
+
+    User::create(&db, user("ann")).await.unwrap();
+    User::create(&db, user("bob")).await.unwrap();
+
+    let mut query = db.query("users");
+    let affected = query
+        .update_with(Rename { name: "anna".to_string(), email: Some("anna@example.com".to_string()) })
+        .r#where("id", "=", 1)
+        .execute()
+        .await
+        .unwrap();
+
+    assert_eq!(affected, 1);
+
+    let mut query = db.query("users");
+    query.update_with_skip_none(Rename { name: "robert".to_string(), email: None }).r#where("id", "=", 2).execute().await.unwrap();
+
+    let mut query = User::query(&db);
+    let users = query.order_by("id", Order::ASC).all().await.unwrap();
+
+    assert_eq!(
+        users.iter().map(|user| (user.name.as_str(), user.email.as_deref())).collect::<Vec<_>>(),
+        vec![("anna", Some("anna@example.com")), ("robert", None)]
+    );
+}
+
+#[tokio::test]
+async fn upserts_only_the_columns_clients_may_set() {
+    let db = common::database("model_upsert", SCHEMA).await;
+
+    for value in ["dark", "light"] {
+        let mut query = Setting::query(&db);
+        query
+            .upsert_with(HashMap::from([("key", Value::from("theme")), ("value", Value::from(value)), ("locked", Value::Bool(true))]), vec!["key"], vec!["value", "locked"])
+            .execute()
+            .await
+            .unwrap();
+    }
+
+    let settings = Setting::all(&db).await.unwrap();
+
+    assert_eq!(settings.iter().map(|setting| (setting.key.as_str(), setting.value.as_str(), setting.locked)).collect::<Vec<_>>(), vec![("theme", "light", false)]);
+
+    let mut query = Setting::query(&db);
+    query.upsert_with(HashMap::from([("key", Value::from("theme")), ("value", Value::from("blue"))]), vec!["key"], vec![]).execute().await.unwrap();
+
+    assert_eq!(Setting::find_or_fail(&db, 1).await.unwrap().value, "light");
+}
+
+#[tokio::test]
+async fn drops_or_rejects_columns_that_are_not_fillable() {
+    let db = common::database("model_fillable", SCHEMA).await;
+
+    let values = |name: &str| HashMap::from([("name", Value::from(name)), ("email", Value::from("admin@example.com"))]);
+
+    Signup::create(&db, values("ann")).await.unwrap();
+    Signup::query(&db).insert_with(values("bob")).execute().await.unwrap();
+    Signup::query(&db).update_with(values("cid")).r#where("id", "=", 2).execute().await.unwrap();
+
+    let mut query = User::query(&db);
+    let users = query.order_by("id", Order::ASC).all().await.unwrap();
+
+    assert_eq!(users.iter().map(|user| (user.name.as_str(), user.email.as_deref())).collect::<Vec<_>>(), vec![("ann", None), ("cid", None)]);
+
+    let errors = vec![
+        StrictSignup::create(&db, values("dan")).await.unwrap_err(),
+        StrictSignup::query(&db).insert_with(values("dan")).execute().await.unwrap_err(),
+        StrictSignup::query(&db).update_with(values("dan")).r#where("id", "=", 1).execute().await.unwrap_err(),
+    ];
+
+    for error in errors {
+        assert_eq!(error.downcast_ref::<MassAssignment>().map(|error| error.column.as_str()), Some("email"), "{}", error);
+    }
+
+    assert_eq!(User::query(&db).count().await.unwrap(), 2);
+    assert_eq!(User::find_or_fail(&db, 1).await.unwrap().name, "ann");
+}
+
+#[tokio::test]
+async fn reads_rows_back_by_their_generated_or_bound_key() {
+    let db = common::database("model_key_types", SCHEMA).await;
+
+    let generated = Token::create(&db, HashMap::from([("name", Value::from("generated"))])).await.unwrap();
+
+    assert_eq!((generated.id.len(), generated.name.as_str()), (26, "generated"));
+
+    let mut query = Token::query(&db);
+    let bound = query.insert_as::<Token>(vec!["id", "name"]).bind("bound").bind("bound").execute().await.unwrap();
+
+    assert_eq!(bound.id, "bound");
+
+    let mut query = Token::query(&db);
+    let error = query.insert_as::<Token>(vec!["id", "name"]).bind(7).bind("numbered").execute().await.unwrap_err();
+
+    assert!(error.to_string().contains("bind it as text"), "{}", error);
+}
+
+#[tokio::test]
+async fn fails_insert_as_without_a_key_or_rowid() {
+    let db = common::database("model_without_rowid", "CREATE TABLE tags (name TEXT PRIMARY KEY, n INTEGER) WITHOUT ROWID;").await;
+
+    let mut query = db.query("tags");
+    let error = query.insert_as::<(String, i64)>(vec!["name", "n"]).bind("x").bind(1).execute().await.unwrap_err();
+
+    assert!(error.to_string().contains("WITHOUT ROWID"), "{}", error);
+    assert_eq!(db.query("tags").count().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn rejects_updates_of_stale_versions() {
+    let db = common::database("model_versions", SCHEMA).await;
+
+    let mut article = Article::create(&db, HashMap::from([("title", Value::from("first"))])).await.unwrap();
+    let mut stale = article.clone();
+
+    article.title = "second".to_string();
+    article.save(&db).await.unwrap();
+
+    assert_eq!(article.version, 2);
+
+    stale.title = "lost".to_string();
+
+    assert!(stale.save(&db).await.unwrap_err().is::<StaleModel>());
+
+    let mut query = Article::query(&db);
+    let error = query.update(vec!["title"]).bind("lost").r#where("id", "=", article.id).versioned(1).execute().await.unwrap_err();
+
+    assert!(error.is::<StaleModel>());
+
+    let mut query = Article::query(&db);
+    query.update(vec!["title"]).bind("third").r#where("id", "=", article.id).versioned(2).execute().await.unwrap();
+
+    let found = Article::find(&db, article.id).await.unwrap().unwrap();
+
+    assert_eq!((found.title.as_str(), found.version), ("third", 3));
+}
+
+#[tokio::test]
+async fn paginates_with_metadata() {
+    let db = common::database("model_paginate", SCHEMA).await;
+
+    for name in ["a", "b", "c", "d", "e"] {
+        User::create(&db, user(name)).await.unwrap();
+    }
+
+    let mut query = User::query(&db);
+    let page = query.order_by("id", Order::ASC).paginate(2, 2).await.unwrap().links("/users", "page={page}");
+
+    assert_eq!((page.total, page.last_page, page.from, page.to, page.has_more), (5, 3, Some(3), Some(4), true));
+    assert_eq!(page.prev.as_deref(), Some("/users?page=1"));
+    assert_eq!(page.next.as_deref(), Some("/users?page=3"));
+
+    let names = page.map(|user| user.name).items;
+
+    assert_eq!(names, vec!["c", "d"]);
+}
+
+#[tokio::test]
+async fn fills_timestamps() {
+    let db = common::database("model_timestamps", SCHEMA).await;
+
+    let created = Post::create(&db, HashMap::from([("title", Value::from("created"))])).await.unwrap();
+
+    assert!(created.created_at.is_some() && created.created_at == created.updated_at);
+
+    let mut query = Post::query(&db);
+    query.insert(vec!["title"]).bind("inserted").execute().await.unwrap();
+
+    let mut query = Post::query(&db);
+    let inserted = query.insert_as::<Post>(vec!["title"]).bind("returned").execute().await.unwrap();
+
+    assert!(inserted.created_at.is_some() && inserted.updated_at.is_some());
+
+    db.query("posts").update_with(HashMap::from([("updated_at", Value::Null)])).execute().await.unwrap();
+
+    let mut query = Post::query(&db);
+    query.update(vec!["title"]).bind("updated").r#where("id", "=", 2).execute().await.unwrap();
+
+    let mut query = Post::query(&db);
+    query.touch(false).update(vec!["title"]).bind("untouched").r#where("id", "=", 3).execute().await.unwrap();
+
+    let mut query = Post::query(&db);
+    let posts = query.order_by("id", Order::ASC).all().await.unwrap();
+
+    assert!(posts[1].created_at.is_some(), "positional inserts fill created_at");
+    assert_eq!(posts.iter().map(|post| (post.title.as_str(), post.updated_at.is_some())).collect::<Vec<_>>(), vec![
+        ("created", false),
+        ("updated", true),
+        ("untouched", false),
+    ]);
+
+    let mut saved = posts[0].clone();
+    saved.title = "saved".to_string();
+    saved.save(&db).await.unwrap();
+
+    assert!(saved.updated_at.is_some(), "save sets updated_at on the model");
+    assert_eq!(saved.updated_at, Post::find(&db, saved.id).await.unwrap().unwrap().updated_at);
+}
+
+#[tokio::test]
+async fn soft_deletes_and_restores() {
+    let db = common::database("model_soft_deletes", SCHEMA).await;
+
+    let first = Note::create(&db, HashMap::from([("title", Value::from("first"))])).await.unwrap();
+    Note::create(&db, HashMap::from([("title", Value::from("second"))])).await.unwrap();
+
+    first.delete(&db).await.unwrap();
+
+    assert_eq!(Note::all(&db).await.unwrap().iter().map(|note| note.title.as_str()).collect::<Vec<_>>(), vec!["second"]);
+    assert_eq!(Note::query(&db).with_trashed().count().await.unwrap(), 2);
+
+    let mut trashed = Note::query(&db).only_trashed().first_or_fail().await.unwrap();
+
+    assert!(trashed.trashed());
+
+    trashed.restore(&db).await.unwrap();
+
+    assert!(!trashed.trashed());
+    assert_eq!(Note::all(&db).await.unwrap().len(), 2);
+
+    trashed.force_delete(&db).await.unwrap();
+
+    assert_eq!(Note::query(&db).with_trashed().count().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn applies_the_changes_of_updating_hooks() {
+    let db = common::database("model_hooks", SCHEMA).await;
+
+    User::create(&db, HashMap::from([("name", Value::from("ann")), ("email", Value::from("ann@example.com"))])).await.unwrap();
+    Account::observe(KeepEmail);
+
+    let mut account = Account::find_or_fail(&db, 1).await.unwrap();
+
+    account.name = "anna".to_string();
+    account.email = Some("anna@example.com".to_string());
+    account.save(&db).await.unwrap();
+
+    let user = User::find_or_fail(&db, 1).await.unwrap();
+
+    assert_eq!((user.name.as_str(), user.email.as_deref()), ("ANNA", Some("ann@example.com")));
+    assert_eq!(account.name, "ANNA");
+    assert_eq!(account.dirty_fields(), vec!["email"], "a column left out by a hook is still to be saved");
+
+    // Without hooks changing values a model implemented by hand saves without `set_value`.
+    let mut handmade = Handmade { id: 1, name: "bob".to_string() };
+    handmade.save(&db).await.unwrap();
+
+    Handmade::observe(Rejected);
+
+    assert!(handmade.save(&db).await.unwrap_err().to_string().contains("set_value"));
+    assert_eq!(User::find_or_fail(&db, 1).await.unwrap().name, "bob");
+}
+
+#[tokio::test]
+async fn rolls_back_writes_aborted_by_hooks() {
+    let db = common::database("model_aborted_hooks", SCHEMA).await;
+
+    Entry::observe(FailNamed);
+
+    for hook in ["creating", "created"] {
+        let error = Entry::create(&db, HashMap::from([("name", hook)])).await.unwrap_err();
+
+        assert_eq!(error.to_string(), format!("{} failed", hook));
+    }
+
+    assert_eq!(Entry::query(&db).with_trashed().count().await.unwrap(), 0);
+
+    // In the caller's transaction the hooks abort their own savepoint, the caller's queries are kept.
+    let transaction = db.transaction().await.unwrap();
+
+    for name in ["deleting", "deleted", "restored"] {
+        Entry::create(&transaction, HashMap::from([("name", name)])).await.unwrap();
+    }
+
+    assert!(Entry::create(&transaction, HashMap::from([("name", "created")])).await.is_err());
+
+    transaction.commit().await.unwrap();
+
+    let entries = Entry::all(&db).await.unwrap();
+
+    assert_eq!(entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>(), vec!["deleting", "deleted", "restored"]);
+
+    for entry in &entries[..2] {
+        assert_eq!(entry.delete(&db).await.unwrap_err().to_string(), format!("{} failed", entry.name));
+    }
+
+    assert_eq!(Entry::all(&db).await.unwrap().len(), 3);
+
+    let mut restored = entries[2].clone();
+
+    restored.delete(&db).await.unwrap();
+
+    assert_eq!(restored.restore(&db).await.unwrap_err().to_string(), "restored failed");
+    assert_eq!(Entry::query(&db).only_trashed().count().await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn serializes_the_visible_fields() {
+    let db = common::database("model_serialize", SCHEMA).await;
+
+    let login = Login::create(&db, HashMap::from([("name", "ann"), ("password", "secret")])).await.unwrap();
+
+    assert_eq!(serde_json::to_value(&login).unwrap(), json!({"id": 1, "name": "ann"}));
+
+    let page = Login::query(&db).paginate(10, 1).await.unwrap();
+
+    assert_eq!(serde_json::to_value(&page).unwrap()["items"], json!([{"id": 1, "name": "ann"}]));
+    assert_eq!(serde_json::to_value(login.make_visible(&["password"])).unwrap(), json!({"id": 1, "name": "ann", "password": "secret"}));
+
+    let profile = Profile::find(&db, 1).await.unwrap().unwrap();
+
+    assert_eq!(serde_json::to_value(&profile).unwrap(), json!({"id": 1, "displayName": "ann"}));
+    assert_eq!(serde_json::to_value(profile.clone().make_hidden(&["id"])).unwrap(), json!({"displayName": "ann"}));
+    assert_eq!(serde_json::to_value(profile.make_visible(&["password"])).unwrap(), json!({"id": 1, "displayName": "ann", "password": "secret"}));
+}
+
+#[tokio::test]
+async fn applies_local_and_global_scopes() {
+    let db = common::database("model_scopes", SCHEMA).await;
+
+    for (name, email) in [("ann", Some("ann@example.com")), ("bob", None), ("cid", Some("cid@example.com"))] {
+        User::create(&db, HashMap::from([("name", Value::from(name)), ("email", Value::from(email))])).await.unwrap();
+    }
+
+    let mut query = Member::query(&db);
+    let members = query
+        .r#where("name", "=", "ann")
+        .or_where("name", "=", "bob")
+        .scope(|query| { query.r#where("email", "like", "@"); })
+        .all()
+        .await
+        .unwrap();
+
+    assert_eq!(members.iter().map(|member| member.name.as_str()).collect::<Vec<_>>(), vec!["ann"]);
+
+    Member::add_global_scope::<SQLite, _>("not_cid", |query| { query.r#where("name", "!=", "cid"); });
+
+    assert_eq!(Member::all(&db).await.unwrap().len(), 2);
+    assert_eq!(Member::query(&db).without_global_scope("not_cid").count().await.unwrap(), 3);
+
+    Member::remove_global_scope::<SQLite>("not_cid");
+
+    assert_eq!(Member::all(&db).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn applies_global_scopes_and_soft_deletes_to_updates() {
+    let db = common::database("model_scoped_updates", SCHEMA).await;
+
+    for tenant in [1, 2] {
+        Project::create(&db, HashMap::from([("tenant_id", Value::from(tenant)), ("name", Value::from("draft"))])).await.unwrap();
+    }
+
+    Project::add_global_scope::<SQLite, _>("tenant", |query| { query.r#where("tenant_id", "=", 1); });
+
+    assert_eq!(Project::query(&db).update_with(HashMap::from([("name", "one")])).execute().await.unwrap(), 1);
+    assert_eq!(Project::query(&db).update(vec!["name"]).bind("two").r#where("id", "=", 2).or_where("id", "=", 1).execute().await.unwrap(), 1);
+
+    Project::remove_global_scope::<SQLite>("tenant");
+
+    let mut query = Project::query(&db);
+    let projects = query.order_by("id", Order::ASC).all().await.unwrap();
+
+    assert_eq!(projects.iter().map(|project| project.name.as_str()).collect::<Vec<_>>(), vec!["two", "draft"]);
+
+    let trashed = Note::create(&db, HashMap::from([("title", Value::from("trashed"))])).await.unwrap();
+    trashed.delete(&db).await.unwrap();
+
+    assert_eq!(Note::query(&db).update_with(HashMap::from([("title", "renamed")])).execute().await.unwrap(), 0);
+    assert_eq!(Note::query(&db).with_trashed().update_with(HashMap::from([("title", "renamed")])).execute().await.unwrap(), 1);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use flyer_orm::{Model, query::Order};
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "jobs")]
+struct Job {
+    id: i64,
+    status: String,
+}
+
+/// Ignored so a run without MySQL doesn't pass it unchecked, run it with `cargo test -- --ignored`.
+#[tokio::test]
+#[ignore = "needs MYSQL_URL"]
+async fn skips_rows_locked_by_other_transactions() {
+    let db = common::mysql("
+        DROP TABLE IF EXISTS jobs;
+        CREATE TABLE jobs (id BIGINT AUTO_INCREMENT PRIMARY KEY, status TEXT NOT NULL);
+        INSERT INTO jobs (status) VALUES ('pending'), ('pending');
+    ").await.expect("MYSQL_URL is not set");
+
+    let first = db.transaction().await.unwrap();
+    let second = db.transaction().await.unwrap();
+    let claimed = Job::query(&first).r#where("status", "=", "pending").order_by("id", Order::ASC).lock_for_update().skip_locked().first().await.unwrap();
+    let next = Job::query(&second).r#where("status", "=", "pending").order_by("id", Order::ASC).lock_for_update().skip_locked().first().await.unwrap();
+
+    assert_eq!((claimed.map(|job| job.id), next.map(|job| job.id)), (Some(1), Some(2)));
+
+    let third = db.transaction().await.unwrap();
+    let error = Job::query(&third).r#where("id", "=", 1).lock_shared().no_wait().first().await.unwrap_err();
+
+    assert!(error.to_string().contains("NOWAIT"), "{}", error);
+
+    first.commit().await.unwrap();
+    second.commit().await.unwrap();
+    third.rollback().await.unwrap();
+}
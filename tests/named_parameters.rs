@@ -0,0 +1,49 @@
+mod common;
+
+use std::collections::HashMap;
+
+use flyer_orm::Value;
+
+#[derive(Debug, sqlx::FromRow)]
+struct User {
+    name: String,
+}
+
+#[tokio::test]
+async fn binds_repeated_colon_names() {
+    let db = common::database("named_parameters", "
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT);
+        INSERT INTO users (name, email) VALUES ('ann', 'ann@example.com'), ('bob', 'ann');
+    ").await;
+
+    let users: Vec<User> = db.query("users")
+        .query_all_with("SELECT name FROM users WHERE name = :name OR email = :name ORDER BY id", HashMap::from([("name", Value::from("ann"))]))
+        .await
+        .unwrap();
+
+    assert_eq!(users.iter().map(|user| user.name.as_str()).collect::<Vec<_>>(), vec!["ann", "bob"]);
+}
+
+#[tokio::test]
+async fn leaves_strings_casts_and_at_variables() {
+    let db = common::database("named_parameters_untouched", "
+        CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT);
+        INSERT INTO users (name, email) VALUES (':name', '@name');
+    ").await;
+
+    let user: User = db.query("users")
+        .query_one_with("SELECT name FROM users WHERE name = ':name' AND email = '@name' AND id = :id", HashMap::from([("id", Value::Int(1))]))
+        .await
+        .unwrap();
+
+    assert_eq!(user.name, ":name");
+}
+
+#[tokio::test]
+async fn fails_on_missing_names() {
+    let db = common::database("named_parameters_missing", "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);").await;
+
+    let result = db.query("users").query_all_with::<User, _, _>("SELECT name FROM users WHERE name = :name", HashMap::<&str, Value>::new()).await;
+
+    assert!(result.is_err());
+}
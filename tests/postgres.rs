@@ -0,0 +1,136 @@
+mod common;
+
+use std::collections::HashMap;
+
+use flyer_orm::{Model, Value, query::Order};
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "pg_users")]
+struct User {
+    id: i64,
+    name: String,
+    #[sqlx(skip)]
+    #[orm(has_many = "user_id")]
+    posts: Vec<Post>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "pg_posts")]
+struct Post {
+    id: i64,
+    user_id: i64,
+    title: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "pg_user_settings", timestamps)]
+struct Setting {
+    id: i64,
+    key: String,
+    value: String,
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "pg_jobs")]
+struct Job {
+    id: i64,
+    status: String,
+}
+
+const SCHEMA: &str = "
+    DROP TABLE IF EXISTS pg_posts;
+    DROP TABLE IF EXISTS pg_users;
+    CREATE TABLE pg_users (id BIGSERIAL PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE pg_posts (id BIGSERIAL PRIMARY KEY, user_id BIGINT NOT NULL, title TEXT NOT NULL);
+";
+
+/// Ignored so a run without Postgres doesn't pass it unchecked, run it with `cargo test -- --ignored`.
+#[tokio::test]
+#[ignore = "needs POSTGRES_URL"]
+async fn skips_rows_locked_by_other_transactions() {
+    let db = common::postgres("
+        DROP TABLE IF EXISTS pg_jobs;
+        CREATE TABLE pg_jobs (id BIGSERIAL PRIMARY KEY, status TEXT NOT NULL);
+        INSERT INTO pg_jobs (status) VALUES ('pending'), ('pending');
+    ").await.expect("POSTGRES_URL is not set");
+
+    let first = db.transaction().await.unwrap();
+    let second = db.transaction().await.unwrap();
+    let claimed = Job::query(&first).r#where("status", "=", "pending").order_by("id", Order::ASC).lock_for_update().skip_locked().first().await.unwrap();
+    let next = Job::query(&second).r#where("status", "=", "pending").order_by("id", Order::ASC).lock_for_update().skip_locked().first().await.unwrap();
+
+    assert_eq!((claimed.map(|job| job.id), next.map(|job| job.id)), (Some(1), Some(2)));
+
+    let third = db.transaction().await.unwrap();
+    let error = Job::query(&third).r#where("id", "=", 1).no_wait().first().await.unwrap_err();
+
+    assert!(error.to_string().contains("could not obtain lock"), "{}", error);
+
+    first.commit().await.unwrap();
+    second.commit().await.unwrap();
+    third.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn upserts_rows() {
+    let Some(db) = common::postgres("
+        DROP TABLE IF EXISTS pg_user_settings;
+        CREATE TABLE pg_user_settings (id BIGSERIAL PRIMARY KEY, key TEXT NOT NULL UNIQUE, value TEXT NOT NULL, created_at TIMESTAMPTZ, updated_at TIMESTAMPTZ);
+    ").await else {
+        return;
+    };
+
+    for value in ["dark", "light"] {
+        let mut query = Setting::query(&db);
+        query.upsert_with(HashMap::from([("key", Value::from("theme")), ("value", Value::from(value))]), vec!["key"], vec!["value"]).execute().await.unwrap();
+    }
+
+    let settings = Setting::all(&db).await.unwrap();
+
+    assert_eq!(settings.iter().map(|setting| (setting.key.as_str(), setting.value.as_str())).collect::<Vec<_>>(), vec![("theme", "light")]);
+    assert!(settings[0].updated_at.is_some());
+}
+
+#[tokio::test]
+async fn reads_and_writes_models() {
+    let Some(db) = common::postgres(SCHEMA).await else {
+        return;
+    };
+
+    let ann = User::create(&db, HashMap::from([("name", Value::from("ann"))])).await.unwrap();
+    let bob = User::create(&db, HashMap::from([("name", Value::from("bob"))])).await.unwrap();
+
+    for (user, title) in [(&ann, "first"), (&ann, "second"), (&bob, "third")] {
+        Post::create(&db, HashMap::from([("user_id", Value::Int(user.id)), ("title", Value::from(title))])).await.unwrap();
+    }
+
+    assert_eq!((ann.id, ann.name.as_str()), (1, "ann"));
+    assert_eq!(User::find(&db, 2).await.unwrap().unwrap().name, "bob");
+
+    let mut query = User::query(&db);
+    let users = query.r#where("name", "like", "b")
+        .where_has::<Post, _>("posts", |query| { query.r#where("title", "=", "third"); })
+        .with("posts")
+        .all()
+        .await
+        .unwrap();
+
+    assert_eq!(users.iter().map(|user| user.name.as_str()).collect::<Vec<_>>(), vec!["bob"]);
+    assert_eq!(users[0].posts.iter().map(|post| post.title.as_str()).collect::<Vec<_>>(), vec!["third"]);
+
+    assert_eq!(Post::query(&db).r#where("user_id", "=", ann.id).count().await.unwrap(), 2);
+
+    let page = Post::query(&db).paginate(2, 2).await.unwrap();
+
+    assert_eq!((page.total, page.items.len()), (3, 1));
+
+    let mut query = Post::query(&db);
+    let updated = query.update_with(HashMap::from([("title", Value::from("renamed"))])).r#where("user_id", "=", ann.id).execute().await.unwrap();
+
+    assert_eq!(updated, 2);
+
+    Post::query(&db).r#where("user_id", "=", bob.id).delete().await.unwrap();
+
+    assert_eq!(Post::all(&db).await.unwrap().iter().map(|post| post.title.as_str()).collect::<Vec<_>>(), vec!["renamed", "renamed"]);
+}
@@ -0,0 +1,244 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::collections::HashMap;
+
+use flyer_orm::{Model, Morphed, Query, Value, query::Order, sqlite::SQLite};
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "users")]
+struct User {
+    id: i64,
+    name: String,
+    #[sqlx(skip)]
+    #[orm(has_many = "user_id")]
+    posts: Vec<Post>,
+    #[sqlx(skip)]
+    #[orm(has_one = "user_id")]
+    profile: Option<Profile>,
+    #[sqlx(skip)]
+    #[orm(belongs_to_many(pivot = "role_user", foreign_key = "user_id", related_key = "role_id"))]
+    roles: Vec<Role>,
+    #[orm(computed)]
+    #[sqlx(default)]
+    posts_count: Option<i64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "posts", morph_name = "post")]
+struct Post {
+    id: i64,
+    user_id: i64,
+    title: String,
+    #[sqlx(skip)]
+    #[orm(belongs_to = "user_id")]
+    user: Option<User>,
+    #[sqlx(skip)]
+    #[orm(morph_many = "commentable")]
+    comments: Vec<Comment>,
+}
+
+#[derive(Debug, Clone, Model)]
+#[orm(table = "profiles")]
+struct Profile {
+    id: i64,
+    user_id: i64,
+    bio: String,
+    public: bool,
+}
+
+/// Decoded by hand, so eager loading has to go through `FromRow` to read the same model a query does.
+impl <'r, R: sqlx::Row>sqlx::FromRow<'r, R> for Profile
+where
+    &'r str: sqlx::ColumnIndex<R>,
+    i64: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+    String: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>,
+    bool: sqlx::Decode<'r, R::Database> + sqlx::Type<R::Database>
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        return Ok(Self {
+            id: row.try_get("id")?,
+            user_id: row.try_get("user_id")?,
+            bio: row.try_get::<String, _>("bio")?.trim().to_string(),
+            public: row.try_get("public")?,
+        });
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "roles")]
+struct Role {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Model)]
+#[orm(table = "comments")]
+struct Comment {
+    id: i64,
+    commentable_type: String,
+    commentable_id: i64,
+    body: String,
+    #[sqlx(skip)]
+    #[orm(morph_to)]
+    commentable: Option<Morphed>,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE posts (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL, title TEXT NOT NULL);
+    CREATE TABLE profiles (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL, bio TEXT NOT NULL, public BOOLEAN NOT NULL DEFAULT 1);
+    CREATE TABLE roles (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+    CREATE TABLE role_user (user_id INTEGER NOT NULL, role_id INTEGER NOT NULL, granted_by TEXT);
+    CREATE TABLE comments (id INTEGER PRIMARY KEY, commentable_type TEXT NOT NULL, commentable_id INTEGER NOT NULL, body TEXT NOT NULL);
+
+    INSERT INTO users (name) VALUES ('ann'), ('bob');
+    INSERT INTO posts (user_id, title) VALUES (1, 'first'), (1, 'second'), (2, 'third');
+    INSERT INTO profiles (user_id, bio) VALUES (1, ' hello ');
+    INSERT INTO roles (name) VALUES ('admin'), ('editor'), ('viewer');
+    INSERT INTO comments (commentable_type, commentable_id, body) VALUES ('post', 1, 'nice'), ('post', 1, 'agreed'), ('post', 3, 'meh');
+";
+
+fn titles(posts: &[Post]) -> Vec<&str> {
+    return posts.iter().map(|post| post.title.as_str()).collect();
+}
+
+#[tokio::test]
+async fn queries_relations() {
+    let db = common::database("relations", SCHEMA).await;
+    let ann = User::find_or_fail(&db, 1).await.unwrap();
+
+    let mut query = ann.has_many::<Post, _>(&db, "user_id");
+    assert_eq!(titles(&query.order_by("id", Order::ASC).all().await.unwrap()), vec!["first", "second"]);
+
+    let mut query = ann.has_one::<Profile, _>(&db, "user_id");
+    assert_eq!(query.first().await.unwrap().unwrap().bio, "hello");
+
+    let post = Post::find_or_fail(&db, 3).await.unwrap();
+    let mut query = post.belongs_to::<User, _>(&db, "user_id");
+    assert_eq!(query.first().await.unwrap().unwrap().name, "bob");
+}
+
+#[tokio::test]
+async fn attaches_through_pivot_tables() {
+    let db = common::database("relations_pivot", SCHEMA).await;
+    let ann = User::find_or_fail(&db, 1).await.unwrap();
+    let roles = |user: &User| user.belongs_to_many::<Role, _>(&db, "role_user", "user_id", "role_id");
+
+    roles(&ann).attach(vec![1, 2]).await.unwrap();
+    roles(&ann).detach(vec![1]).await.unwrap();
+    roles(&ann).toggle(vec![2, 3]).await.unwrap();
+
+    let mut query = roles(&ann);
+    assert_eq!(query.all().await.unwrap().iter().map(|role| role.name.as_str()).collect::<Vec<_>>(), vec!["viewer"]);
+
+    roles(&ann).sync(vec![1, 3]).await.unwrap();
+
+    let mut query = roles(&ann);
+    query.order_by("roles.id", Order::ASC);
+    let pivoted = query.with_pivot(vec!["granted_by"]).all_with_pivot().await.unwrap();
+
+    assert_eq!(pivoted.iter().map(|pivoted| pivoted.model.name.as_str()).collect::<Vec<_>>(), vec!["admin", "viewer"]);
+    assert_eq!(pivoted[0].pivot.get("role_id"), Some(&Value::Int(1)));
+    assert_eq!(pivoted[0].pivot.get("granted_by"), Some(&Value::Null));
+}
+
+#[tokio::test]
+async fn eager_loads_nested_relations() {
+    let db = common::database("relations_eager", SCHEMA).await;
+    User::find_or_fail(&db, 1).await.unwrap().belongs_to_many::<Role, _>(&db, "role_user", "user_id", "role_id").attach(vec![2]).await.unwrap();
+
+    let mut query = User::query(&db);
+    let users = query
+        .with("posts.comments")
+        .with("profile")
+        .with("roles")
+        .with_where("posts", |query: &mut Query<SQLite, Post>| { query.r#where("title", "!=", "second"); })
+        .order_by("id", Order::ASC)
+        .all()
+        .await
+        .unwrap();
+
+    assert_eq!(titles(&users[0].posts), vec!["first"]);
+    assert_eq!(users[0].posts[0].comments.iter().map(|comment| comment.body.as_str()).collect::<Vec<_>>(), vec!["nice", "agreed"]);
+    assert_eq!(users[0].profile.as_ref().map(|profile| (profile.bio.as_str(), profile.public)), Some(("hello", true)));
+    assert_eq!(users[0].roles.iter().map(|role| role.name.as_str()).collect::<Vec<_>>(), vec!["editor"]);
+    assert!(users[1].profile.is_none() && users[1].roles.is_empty());
+
+    let mut query = Post::query(&db);
+    let posts = query.with("user").all().await.unwrap();
+
+    assert_eq!(posts.iter().map(|post| post.user.as_ref().unwrap().name.as_str()).collect::<Vec<_>>(), vec!["ann", "ann", "bob"]);
+
+    let mut query = User::query(&db);
+    assert!(query.with("unknown").all().await.is_err());
+}
+
+#[tokio::test]
+async fn filters_and_counts_by_relations() {
+    let db = common::database("relations_exists", SCHEMA).await;
+
+    let mut query = User::query(&db);
+    let users = query.where_has::<Profile, _>("profile", |_| {}).all().await.unwrap();
+
+    assert_eq!(users.iter().map(|user| user.name.as_str()).collect::<Vec<_>>(), vec!["ann"]);
+
+    let mut query = User::query(&db);
+    let users = query.where_doesnt_have::<Post, _>("posts", |query| { query.r#where("title", "=", "third"); }).all().await.unwrap();
+
+    assert_eq!(users.iter().map(|user| user.name.as_str()).collect::<Vec<_>>(), vec!["ann"]);
+
+    let mut query = User::query(&db);
+    let users = query.with_count::<Post>("posts").order_by("id", Order::ASC).all().await.unwrap();
+
+    assert_eq!(users.iter().map(|user| user.posts_count).collect::<Vec<_>>(), vec![Some(2), Some(1)]);
+
+    // The count is selected before the where clause, its values are passed first whichever is added first.
+    let mut query = User::query(&db);
+    let users = query
+        .r#where("name", "=", "ann")
+        .with_count_where::<Post, _>("posts", |query| { query.r#where("title", "!=", "first"); })
+        .all()
+        .await
+        .unwrap();
+
+    assert_eq!(users.iter().map(|user| (user.name.as_str(), user.posts_count)).collect::<Vec<_>>(), vec![("ann", Some(1))]);
+
+    let mut query = User::query(&db);
+    assert!(query.where_has::<Post, _>("unknown", |_| {}).all().await.is_err());
+
+    let mut query = User::query(&db);
+    assert!(query.with_count::<Role>("posts").count().await.is_err());
+}
+
+#[tokio::test]
+async fn loads_polymorphic_relations() {
+    let db = common::database("relations_morph", SCHEMA).await;
+
+    Post::register_morph::<SQLite>();
+
+    let post = Post::find_or_fail(&db, 1).await.unwrap();
+    let mut query = post.morph_many::<Comment, _>(&db, "commentable");
+
+    assert_eq!(query.count().await.unwrap(), 2);
+
+    let comment = Comment::find_or_fail(&db, 3).await.unwrap();
+    let morphed = comment.morph_to(&db, "commentable").await.unwrap().unwrap();
+
+    assert!(morphed.is::<Post>());
+    assert_eq!(morphed.downcast_ref::<Post>().unwrap().title, "third");
+
+    let mut query = Comment::query(&db);
+    let comments = query.with("commentable").all().await.unwrap();
+
+    assert_eq!(comments[0].commentable.as_ref().map(|morphed| morphed.key().clone()), Some(Value::Int(1)));
+
+    let created = Comment::create(&db, HashMap::from([
+        ("commentable_type", Value::from("post")),
+        ("commentable_id", Value::Int(2)),
+        ("body", Value::from("late")),
+    ])).await.unwrap();
+
+    assert_eq!(created.commentable_id, 2);
+}
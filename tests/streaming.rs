@@ -0,0 +1,69 @@
+mod common;
+
+use flyer_orm::query::Order;
+use futures::TryStreamExt;
+
+#[derive(Debug, sqlx::FromRow)]
+struct Item {
+    id: i64,
+    name: String,
+}
+
+const ROWS: &str = "INSERT INTO {table} (name) VALUES ('a'), ('b'), ('c'), ('d'), ('e');";
+
+/// The same checks for every driver, a macro since the queries borrow the database for their whole lifetime.
+macro_rules! streams_and_chunks {
+    ($db:expr, $table:expr) => {
+        let db = $db;
+
+        let mut query = db.query($table);
+        query.order_by("id", Order::ASC);
+        let names = query.stream::<Item>().map_ok(|item| item.name).try_collect::<Vec<_>>().await.unwrap();
+
+        assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+
+        let mut chunks = Vec::new();
+        let mut query = db.query($table);
+        query.order_by("id", Order::ASC);
+        query.chunk::<Item, _, _>(2, |items| {
+            chunks.push(items.into_iter().map(|item| item.name).collect::<Vec<_>>());
+            async { Ok(()) }
+        }).await.unwrap();
+
+        assert_eq!(chunks, vec![vec!["a", "b"], vec!["c", "d"], vec!["e"]]);
+
+        let mut ids = Vec::new();
+        let mut query = db.query($table);
+        query.chunk_by_id::<Item, _, _>(3, |items| {
+            ids.push(items.into_iter().map(|item| item.id).collect::<Vec<_>>());
+            async { Ok(()) }
+        }).await.unwrap();
+
+        assert_eq!(ids, vec![vec![1, 2, 3], vec![4, 5]]);
+    };
+}
+
+#[tokio::test]
+async fn sqlite() {
+    let db = common::database("streaming", &format!("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT); {}", ROWS.replace("{table}", "items"))).await;
+
+    streams_and_chunks!(db, "items");
+}
+
+#[tokio::test]
+async fn postgres() {
+    let Some(db) = common::postgres(&format!("DROP TABLE IF EXISTS streaming_items; CREATE TABLE streaming_items (id BIGSERIAL PRIMARY KEY, name TEXT); {}", ROWS.replace("{table}", "streaming_items"))).await else {
+        return;
+    };
+
+    streams_and_chunks!(db, "streaming_items");
+}
+
+#[tokio::test]
+async fn mysql() {
+    let Some(db) = common::mysql(&format!("DROP TABLE IF EXISTS streaming_items; CREATE TABLE streaming_items (id BIGINT AUTO_INCREMENT PRIMARY KEY, name TEXT); {}", ROWS.replace("{table}", "streaming_items"))).await else {
+        return;
+    };
+
+    streams_and_chunks!(db, "streaming_items");
+}
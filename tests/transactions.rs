@@ -0,0 +1,103 @@
+mod common;
+
+use std::{collections::HashMap, time::Duration};
+
+use flyer_orm::{Value, query::Order};
+use futures::StreamExt;
+
+#[derive(Debug, sqlx::FromRow)]
+struct Account {
+    id: i64,
+    name: String,
+}
+
+/// The same checks for every driver, a macro since the queries borrow the database for their whole lifetime.
+macro_rules! commits_and_rolls_back {
+    ($db:expr, $table:expr) => {
+        let db = $db;
+
+        let transaction = db.transaction().await.unwrap();
+        transaction.query($table).insert_with(HashMap::from([("name", Value::from("kept"))])).execute().await.unwrap();
+
+        // A transaction started in another is a savepoint, rolling it back keeps the outer one's queries.
+        let nested = transaction.transaction().await.unwrap();
+        nested.query($table).insert_with(HashMap::from([("name", Value::from("nested"))])).execute().await.unwrap();
+        nested.rollback().await.unwrap();
+
+        transaction.commit().await.unwrap();
+
+        let transaction = db.transaction().await.unwrap();
+        transaction.query($table).insert_with(HashMap::from([("name", Value::from("rolled back"))])).execute().await.unwrap();
+        transaction.rollback().await.unwrap();
+
+        let transaction = db.transaction().await.unwrap();
+        transaction.query($table).insert_with(HashMap::from([("name", Value::from("dropped"))])).execute().await.unwrap();
+        drop(transaction);
+
+        let mut query = db.query($table);
+        let names = query.order_by("id", Order::ASC).all::<Account>().await.unwrap();
+
+        assert_eq!(names.into_iter().map(|account| account.name).collect::<Vec<_>>(), vec!["kept"]);
+
+        // Queries of the transaction run while its stream and chunk are open, instead of waiting on its connection.
+        let transaction = db.transaction().await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            let mut query = transaction.query($table);
+            let mut accounts = query.stream::<Account>();
+
+            while let Some(account) = accounts.next().await {
+                transaction.query($table).update_with(HashMap::from([("name", Value::from("streamed"))])).r#where("id", "=", account.unwrap().id).execute().await.unwrap();
+            }
+
+            let mut query = transaction.query($table);
+            query.chunk::<Account, _, _>(10, |accounts| {
+                let transaction = &transaction;
+
+                async move {
+                    for account in accounts {
+                        transaction.query($table).update_with(HashMap::from([("name", Value::from("chunked"))])).r#where("id", "=", account.id).execute().await?;
+                    }
+
+                    Ok(())
+                }
+            }).await.unwrap();
+        }).await.unwrap();
+
+        transaction.commit().await.unwrap();
+
+        let mut query = db.query($table);
+        assert_eq!(query.all::<Account>().await.unwrap()[0].name, "chunked");
+    };
+}
+
+#[tokio::test]
+async fn sqlite() {
+    let db = common::database("transactions", "CREATE TABLE accounts (id INTEGER PRIMARY KEY, name TEXT NOT NULL);").await;
+
+    commits_and_rolls_back!(db, "accounts");
+}
+
+#[tokio::test]
+async fn postgres() {
+    let Some(db) = common::postgres("
+        DROP TABLE IF EXISTS pg_accounts;
+        CREATE TABLE pg_accounts (id BIGSERIAL PRIMARY KEY, name TEXT NOT NULL);
+    ").await else {
+        return;
+    };
+
+    commits_and_rolls_back!(db, "pg_accounts");
+}
+
+#[tokio::test]
+async fn mysql() {
+    let Some(db) = common::mysql("
+        DROP TABLE IF EXISTS accounts;
+        CREATE TABLE accounts (id BIGINT AUTO_INCREMENT PRIMARY KEY, name TEXT NOT NULL);
+    ").await else {
+        return;
+    };
+
+    commits_and_rolls_back!(db, "accounts");
+}